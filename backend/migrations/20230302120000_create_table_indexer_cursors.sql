@@ -0,0 +1,7 @@
+CREATE TABLE indexer_cursors
+(
+    name       TEXT PRIMARY KEY,
+    tx_seq     BIGINT      NOT NULL,
+    event_seq  BIGINT      NOT NULL,
+    updated_at timestamptz NOT NULL
+);
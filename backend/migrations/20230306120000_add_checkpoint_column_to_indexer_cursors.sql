@@ -0,0 +1,4 @@
+ALTER TABLE indexer_cursors
+    ADD COLUMN checkpoint BIGINT,
+    ALTER COLUMN tx_seq DROP NOT NULL,
+    ALTER COLUMN event_seq DROP NOT NULL;
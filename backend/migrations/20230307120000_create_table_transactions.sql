@@ -0,0 +1,14 @@
+CREATE TABLE transactions
+(
+    digest           TEXT PRIMARY KEY,
+    sender           TEXT        NOT NULL,
+    timestamp        timestamptz NOT NULL,
+    checkpoint       BIGINT,
+    gas_used         BIGINT      NOT NULL,
+    status           TEXT        NOT NULL,
+    move_calls       JSONB       NOT NULL,
+    affected_objects TEXT[]      NOT NULL
+);
+
+CREATE INDEX transactions_sender_timestamp_idx ON transactions (sender, timestamp DESC);
+CREATE INDEX transactions_affected_objects_idx ON transactions USING GIN (affected_objects);
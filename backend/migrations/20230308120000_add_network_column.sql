@@ -0,0 +1,19 @@
+-- Rows indexed before networks were introduced come from devnet.
+ALTER TABLE nfts
+    ADD COLUMN network TEXT NOT NULL DEFAULT 'devnet';
+ALTER TABLE nfts
+    ALTER COLUMN network DROP DEFAULT;
+ALTER TABLE nfts
+    DROP CONSTRAINT nft_tokens_pkey,
+    ADD PRIMARY KEY (network, id);
+
+ALTER TABLE transactions
+    ADD COLUMN network TEXT NOT NULL DEFAULT 'devnet';
+ALTER TABLE transactions
+    ALTER COLUMN network DROP DEFAULT;
+ALTER TABLE transactions
+    DROP CONSTRAINT transactions_pkey,
+    ADD PRIMARY KEY (network, digest);
+
+DROP INDEX transactions_sender_timestamp_idx;
+CREATE INDEX transactions_network_sender_timestamp_idx ON transactions (network, sender, timestamp DESC);
@@ -0,0 +1,11 @@
+-- Addresses and object ids are stored as `0x` followed by 40 lowercase hex digits,
+-- rows inserted through mutations before ids were validated may lack leading zeros.
+UPDATE nfts
+SET id = '0x' || lpad(lower(regexp_replace(id, '^0[xX]', '')), 40, '0')
+WHERE id !~ '^0x[0-9a-f]{40}$';
+UPDATE nfts
+SET owner = '0x' || lpad(lower(regexp_replace(owner, '^0[xX]', '')), 40, '0')
+WHERE owner !~ '^0x[0-9a-f]{40}$';
+UPDATE nfts
+SET attached_to = '0x' || lpad(lower(regexp_replace(attached_to, '^0[xX]', '')), 40, '0')
+WHERE attached_to !~ '^0x[0-9a-f]{40}$';
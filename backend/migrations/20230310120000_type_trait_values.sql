@@ -0,0 +1,19 @@
+-- Trait flavours used to be plain strings, now they carry their type.
+CREATE FUNCTION pg_temp.typed_traits(traits JSONB) RETURNS JSONB AS
+$$
+SELECT COALESCE(jsonb_agg(
+                        CASE
+                            WHEN jsonb_typeof(t.value -> 'flavour') = 'string'
+                                THEN jsonb_set(t.value, '{flavour}',
+                                               jsonb_build_object('type', 'string', 'value', t.value -> 'flavour'))
+                            ELSE t.value
+                            END ORDER BY t.idx), '[]'::jsonb)
+FROM jsonb_array_elements(traits) WITH ORDINALITY AS t(value, idx)
+$$ LANGUAGE SQL;
+
+UPDATE nfts
+SET traits = pg_temp.typed_traits(traits),
+    items  = (SELECT COALESCE(jsonb_agg(
+                                      jsonb_set(i.value, '{traits}', pg_temp.typed_traits(i.value -> 'traits'))
+                                      ORDER BY i.idx), '[]'::jsonb)
+              FROM jsonb_array_elements(items) WITH ORDINALITY AS i(value, idx));
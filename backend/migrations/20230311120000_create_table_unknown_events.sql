@@ -0,0 +1,13 @@
+CREATE TABLE unknown_events
+(
+    network    TEXT        NOT NULL,
+    tx_digest  TEXT        NOT NULL,
+    event_seq  BIGINT      NOT NULL,
+    type       TEXT        NOT NULL,
+    sender     TEXT        NOT NULL,
+    fields     JSONB       NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (network, tx_digest, event_seq)
+);
+
+CREATE INDEX unknown_events_type_idx ON unknown_events (network, type);
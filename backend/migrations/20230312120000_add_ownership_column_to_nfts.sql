@@ -0,0 +1,30 @@
+-- Direct owner of the NFT, `owner` becomes the wallet it ultimately belongs to.
+ALTER TABLE nfts
+    ADD COLUMN ownership JSONB;
+
+UPDATE nfts
+SET ownership = CASE
+                    WHEN attached_to IS NULL THEN jsonb_build_object('kind', 'address_owner', 'address', owner)
+                    ELSE jsonb_build_object('kind', 'object_owner', 'object', attached_to)
+    END;
+
+-- Attached items belong to the owner of their lemon.
+UPDATE nfts
+SET owner = lemon.owner
+FROM nfts lemon
+WHERE nfts.attached_to = lemon.id
+  AND nfts.network = lemon.network;
+
+-- Copies of the items kept in `items` are owned by the NFT holding them.
+UPDATE nfts
+SET items = (SELECT COALESCE(jsonb_agg(
+                                     i.value || jsonb_build_object(
+                                             'owner', nfts.owner,
+                                             'ownership', jsonb_build_object('kind', 'object_owner', 'object', nfts.id))
+                                     ORDER BY i.idx), '[]'::jsonb)
+             FROM jsonb_array_elements(items) WITH ORDINALITY AS i(value, idx));
+
+ALTER TABLE nfts
+    ALTER COLUMN ownership SET NOT NULL;
+
+CREATE INDEX nfts_ownership_object_idx ON nfts (network, (ownership ->> 'object'));
@@ -0,0 +1,37 @@
+-- Slot an attached item takes, a lemon holds at most one item per slot.
+ALTER TABLE nfts
+    ADD COLUMN slot TEXT DEFAULT NULL;
+
+-- Attached items take the slot of their `slot` trait, the earliest attached one wins
+-- when several items of a lemon share it.
+UPDATE nfts
+SET slot = s.slot
+FROM (SELECT network,
+             id,
+             slot,
+             row_number() OVER (PARTITION BY network, attached_to, slot ORDER BY created_at, id) AS n
+      FROM (SELECT network,
+                   id,
+                   attached_to,
+                   created_at,
+                   (SELECT t -> 'flavour' ->> 'value'
+                    FROM jsonb_array_elements(traits) AS t
+                    WHERE t ->> 'name' = 'slot'
+                      AND t -> 'flavour' ->> 'type' = 'string'
+                    LIMIT 1) AS slot
+            FROM nfts
+            WHERE attached_to IS NOT NULL) AS attached
+      WHERE slot IN ('head', 'weapon', 'back')) AS s
+WHERE nfts.network = s.network
+  AND nfts.id = s.id
+  AND s.n = 1;
+
+-- Copies of the items kept in `items` take the slot too.
+UPDATE nfts
+SET items = (SELECT COALESCE(jsonb_agg(i.value || jsonb_build_object('slot', item.slot) ORDER BY i.idx),
+                             '[]'::jsonb)
+             FROM jsonb_array_elements(nfts.items) WITH ORDINALITY AS i(value, idx)
+                      LEFT JOIN nfts item ON item.id = i.value ->> 'id' AND item.network = nfts.network);
+
+CREATE UNIQUE INDEX nfts_attached_to_slot_idx ON nfts (network, attached_to, slot)
+    WHERE attached_to IS NOT NULL AND slot IS NOT NULL;
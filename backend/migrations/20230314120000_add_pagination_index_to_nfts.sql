@@ -0,0 +1,2 @@
+-- Pages of the nfts connection are taken in (created_at, id) order.
+CREATE INDEX nfts_created_at_id_idx ON nfts (network, created_at, id);
@@ -0,0 +1,5 @@
+-- Indexes backing the filters of the nfts query.
+CREATE INDEX nfts_traits_idx ON nfts USING GIN (traits jsonb_path_ops);
+CREATE INDEX nfts_network_owner_idx ON nfts (network, owner);
+CREATE INDEX nfts_network_type_idx ON nfts (network, type);
+CREATE INDEX nfts_network_attached_to_idx ON nfts (network, attached_to);
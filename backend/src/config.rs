@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Context, Error, Result};
+use models::Network;
 use serde::Deserialize;
 use sqlx::postgres::PgConnectOptions;
 use std::str::FromStr;
@@ -42,6 +43,9 @@ impl DatabaseConfig {
 #[derive(Deserialize, Clone, Debug)]
 pub struct GraphQlConfig {
     pub playground_route: String,
+    /// Network used by queries and mutations which don't specify one.
+    #[serde(default)]
+    pub default_network: Network,
 }
 
 pub fn load_config() -> Result<Config> {
@@ -1,61 +1,337 @@
-use anyhow::{Context as _, Result};
-use async_graphql::{Context, Object};
-use models::{Nft, NftSql, Trait};
-use sqlx::{query, query_as, types::Json, PgPool, Postgres, Transaction};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use async_graphql::connection::{self, Connection, Edge, OpaqueCursor};
+use async_graphql::{Context, Object, SimpleObject, ID};
+use chrono::{DateTime, Utc};
+use models::{
+    Address, GlobalId, MoveCall, Network, Nft, NftFilter, NftKind, NftOrder, NftOrderField,
+    NftPatch, NftSql, Node, ObjectId, OrderDirection, Ownership, Slot, Trait, TraitValue,
+    TransactionRecord, TransactionSql, UnknownEvent, UnknownEventCount, UnknownEventRecord,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::{query, query_as, types::Json, PgPool, Postgres, QueryBuilder, Row, Transaction};
 use std::result::Result as StdResult;
 
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Position of an NFT in the `nfts` connection, only valid for the order it was taken in.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct NftCursor {
+    field: NftOrderField,
+    r#trait: Option<String>,
+    direction: OrderDirection,
+    key: SortKey,
+    id: ObjectId,
+}
+
+/// Value the NFT is sorted by before its id, see `sort_columns`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+enum SortKey {
+    Id,
+    CreatedAt(DateTime<Utc>),
+    Owner(Address),
+    ItemCount(i64),
+    /// Numbers come before strings and bools, which come before NFTs without the trait.
+    Trait {
+        number: Option<String>,
+        text: Option<String>,
+    },
+}
+
+impl NftCursor {
+    fn new(nft: &Nft, order: &NftOrder) -> Self {
+        let key = match order.field {
+            NftOrderField::CreatedAt => SortKey::CreatedAt(nft.created_at),
+            NftOrderField::Id => SortKey::Id,
+            NftOrderField::Owner => SortKey::Owner(nft.owner.clone()),
+            NftOrderField::ItemCount => SortKey::ItemCount(nft.items.len() as i64),
+            NftOrderField::Trait => {
+                let flavour = nft
+                    .traits
+                    .iter()
+                    .find(|t| Some(&t.name) == order.r#trait.as_ref())
+                    .map(|t| &t.flavour);
+                let (number, text) = match flavour {
+                    Some(TraitValue::Number(value)) => (Some(value.to_string()), None),
+                    Some(TraitValue::BigNumber(value)) => (Some(value.clone()), None),
+                    Some(TraitValue::String(value)) => (None, Some(value.clone())),
+                    Some(TraitValue::Bool(value)) => (None, Some(value.to_string())),
+                    _ => (None, None),
+                };
+                SortKey::Trait { number, text }
+            }
+        };
+
+        Self {
+            field: order.field,
+            r#trait: order.r#trait.clone(),
+            direction: order.direction,
+            key,
+            id: nft.id.clone(),
+        }
+    }
+
+    fn is_taken_in(&self, order: &NftOrder) -> bool {
+        self.field == order.field
+            && self.r#trait == order.r#trait
+            && self.direction == order.direction
+    }
+}
+
+#[derive(SimpleObject, Debug)]
+struct NftConnectionFields {
+    /// Number of NFTs matching the filters, regardless of the page.
+    total_count: i64,
+}
+
+type NftConnection = Connection<OpaqueCursor<NftCursor>, Nft, NftConnectionFields>;
+
 pub struct QueryRoot;
 
 #[Object]
 impl QueryRoot {
+    /// NFTs owned by `owner` directly, or through other objects as well when `indirect` is set.
+    ///
+    /// Pages are taken with `first`/`after` forwards and `last`/`before` backwards, at most
+    /// 100 NFTs at a time, `first` and `last` can't be both given. Cursors are only valid for the
+    /// `orderBy` they were given with.
+    #[allow(clippy::too_many_arguments)]
     async fn nfts(
         &self,
         ctx: &Context<'_>,
-        owner: Option<String>,
-        r#type: Option<String>,
-    ) -> Result<Vec<Nft>> {
+        owner: Option<Address>,
+        #[graphql(default)] indirect: bool,
+        r#type: Option<NftKind>,
+        #[graphql(default)] filter: NftFilter,
+        #[graphql(default)] order_by: NftOrder,
+        network: Option<Network>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> Result<NftConnection> {
+        if let (true, Some(attached_to)) = (filter.unattached, &filter.attached_to) {
+            bail!("NFTs can't be both unattached and attached to `{attached_to}`");
+        }
+        ensure!(
+            order_by.field != NftOrderField::Trait || order_by.r#trait.is_some(),
+            "Ordering by trait requires the trait's name"
+        );
+        ensure!(
+            first.is_none() || last.is_none(),
+            "The \"first\" and \"last\" parameters cannot exist at the same time"
+        );
         let pool = ctx.data_unchecked::<PgPool>();
-        let tokens = get_nfts_db(pool, owner, r#type)
-            .await
-            .context("Failed to get nfts data from database")?;
+        let args = NftsArgs {
+            owner,
+            indirect,
+            r#type,
+            filter,
+            order_by,
+            network: network_or_default(ctx, network),
+        };
+        let with_total_count = ctx.look_ahead().field("totalCount").exists();
+        connection::query(after, before, first, last, |after, before, first, last| {
+            get_nfts_page(pool, args, with_total_count, after, before, first, last)
+        })
+        .await
+        .map_err(|err| anyhow!(err.message))
+    }
 
-        Ok(tokens)
+    /// Relay's refetching of an object by its global id, the `globalId` of the [`Node`].
+    async fn node(&self, ctx: &Context<'_>, global_id: ID) -> Result<Option<Node>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let GlobalId::Nft { network, id } = global_id
+            .parse()
+            .context("Failed to parse node's global id")?;
+        match get_nft_db(id, network, pool).await {
+            Ok(nft) => Ok(Some(Node::Nft(nft))),
+            Err(sqlx::Error::RowNotFound) => Ok(None),
+            Err(err) => Err(err).context("Failed to get nft data from database"),
+        }
     }
 
-    async fn nft(&self, ctx: &Context<'_>, id: String) -> Result<Nft> {
+    async fn nft(&self, ctx: &Context<'_>, id: ObjectId, network: Option<Network>) -> Result<Nft> {
         let pool = ctx.data_unchecked::<PgPool>();
-        let token = get_nft_db(id, pool)
+        let token = get_nft_db(id, network_or_default(ctx, network), pool)
             .await
             .context("Failed to get nft data from database")?;
 
         Ok(token)
     }
+
+    /// Transactions which touched the NFT or were sent by the address, newest first.
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        nft_id: Option<ObjectId>,
+        address: Option<Address>,
+        network: Option<Network>,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] limit: i64,
+        #[graphql(default)] offset: i64,
+    ) -> Result<Vec<TransactionRecord>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let network = network_or_default(ctx, network);
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let transactions =
+            get_transactions_db(pool, nft_id, address, network, limit, offset.max(0))
+                .await
+                .context("Failed to get transactions from database")?;
+
+        Ok(transactions)
+    }
+
+    /// Number of stored events of each type the indexer had no handler for.
+    async fn unknown_event_counts(
+        &self,
+        ctx: &Context<'_>,
+        network: Option<Network>,
+    ) -> Result<Vec<UnknownEventCount>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let counts = get_unknown_event_counts_db(pool, network_or_default(ctx, network))
+            .await
+            .context("Failed to get unknown event counts from database")?;
+
+        Ok(counts)
+    }
+
+    /// Stored events the indexer had no handler for, oldest first.
+    async fn unknown_events(
+        &self,
+        ctx: &Context<'_>,
+        r#type: Option<String>,
+        network: Option<Network>,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] limit: i64,
+        #[graphql(default)] offset: i64,
+    ) -> Result<Vec<UnknownEventRecord>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let network = network_or_default(ctx, network);
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        let events = get_unknown_events_db(pool, r#type, network, limit, offset.max(0))
+            .await
+            .context("Failed to get unknown events from database")?;
+
+        Ok(events)
+    }
 }
 
-#[tracing::instrument(name = "Query nft from database", skip_all)]
-async fn get_nfts_db(
+/// The network from the argument, falling back to the configured default.
+fn network_or_default(ctx: &Context<'_>, network: Option<Network>) -> Network {
+    network.unwrap_or_else(|| *ctx.data_unchecked::<Network>())
+}
+
+/// Page of the `nfts` connection, `last` takes the page from the end instead of the start.
+async fn get_nfts_page(
     pool: &PgPool,
-    owner: Option<String>,
-    r#type: Option<String>,
-) -> StdResult<Vec<Nft>, sqlx::Error> {
+    args: NftsArgs,
+    with_total_count: bool,
+    after: Option<OpaqueCursor<NftCursor>>,
+    before: Option<OpaqueCursor<NftCursor>>,
+    first: Option<usize>,
+    last: Option<usize>,
+) -> Result<NftConnection> {
+    let after = after.map(|OpaqueCursor(cursor)| cursor);
+    let before = before.map(|OpaqueCursor(cursor)| cursor);
+    for cursor in after.iter().chain(&before) {
+        ensure!(
+            cursor.is_taken_in(&args.order_by),
+            "The cursor was taken in another order than `orderBy`"
+        );
+    }
+    let backwards = last.is_some();
+    let limit = last
+        .or(first)
+        .map_or(DEFAULT_PAGE_SIZE, |limit| limit as i64)
+        .min(MAX_PAGE_SIZE);
+
+    let total_count = if with_total_count {
+        count_nfts_db(pool, &args)
+            .await
+            .context("Failed to count nfts in database")?
+    } else {
+        0
+    };
+    // One more NFT than asked tells whether there's another page.
+    let mut nfts = get_nfts_db(
+        pool,
+        &args,
+        after.as_ref(),
+        before.as_ref(),
+        limit + 1,
+        backwards,
+    )
+    .await
+    .context("Failed to get nfts data from database")?;
+    let has_more = nfts.len() as i64 > limit;
+    nfts.truncate(limit as usize);
+    if backwards {
+        nfts.reverse();
+    }
+
+    let (has_previous_page, has_next_page) = if backwards {
+        (has_more, before.is_some())
+    } else {
+        (after.is_some(), has_more)
+    };
+    let mut connection = Connection::with_additional_fields(
+        has_previous_page,
+        has_next_page,
+        NftConnectionFields { total_count },
+    );
+    connection.edges.extend(
+        nfts.into_iter()
+            .map(|nft| Edge::new(OpaqueCursor(NftCursor::new(&nft, &args.order_by)), nft)),
+    );
+
+    Ok(connection)
+}
+
+/// Arguments of the `nfts` query besides the page.
+#[derive(Debug)]
+struct NftsArgs {
+    owner: Option<Address>,
+    indirect: bool,
+    r#type: Option<NftKind>,
+    filter: NftFilter,
+    order_by: NftOrder,
+    network: Network,
+}
+
+#[tracing::instrument(name = "Query transactions from database", skip(pool))]
+async fn get_transactions_db(
+    pool: &PgPool,
+    nft_id: Option<ObjectId>,
+    address: Option<Address>,
+    network: Network,
+    limit: i64,
+    offset: i64,
+) -> StdResult<Vec<TransactionRecord>, sqlx::Error> {
     let ret = query_as!(
-        NftSql,
+        TransactionSql,
         r#"
-        SELECT 
-            id,
-            type,
-            owner,
-            url,
-            traits as "traits: Json<Vec<Trait>>",
-            items as "items: Json<Vec<NftSql>>",
-            created_at,
-            attached_to
-        FROM nfts
-        WHERE ($1::text IS null OR owner = $1)
-            AND ($2::text IS null OR type = $2)
+        SELECT
+            digest,
+            sender as "sender: Address",
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls as "move_calls: Json<Vec<MoveCall>>",
+            affected_objects as "affected_objects: Vec<ObjectId>"
+        FROM transactions
+        WHERE ($1::text IS null OR affected_objects @> ARRAY[$1])
+            AND ($2::text IS null OR sender = $2)
+            AND network = $5
+        ORDER BY timestamp DESC, digest
+        LIMIT $3
+        OFFSET $4
         "#,
-        owner,
-        r#type,
+        nft_id as _,
+        address as _,
+        limit,
+        offset,
+        network.as_str(),
     )
     .fetch_all(pool)
     .await?
@@ -66,28 +342,300 @@ async fn get_nfts_db(
     Ok(ret)
 }
 
+#[tracing::instrument(name = "Query unknown event counts from database", skip(pool))]
+async fn get_unknown_event_counts_db(
+    pool: &PgPool,
+    network: Network,
+) -> StdResult<Vec<UnknownEventCount>, sqlx::Error> {
+    query_as!(
+        UnknownEventCount,
+        r#"
+        SELECT type, COUNT(*) as "count!"
+        FROM unknown_events
+        WHERE network = $1
+        GROUP BY type
+        ORDER BY type
+        "#,
+        network.as_str(),
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Query unknown events from database", skip(pool))]
+async fn get_unknown_events_db(
+    pool: &PgPool,
+    r#type: Option<String>,
+    network: Network,
+    limit: i64,
+    offset: i64,
+) -> StdResult<Vec<UnknownEventRecord>, sqlx::Error> {
+    query_as!(
+        UnknownEventRecord,
+        r#"
+        SELECT
+            tx_digest,
+            event_seq,
+            type,
+            sender as "sender: Address",
+            fields,
+            created_at
+        FROM unknown_events
+        WHERE ($1::text IS null OR type = $1)
+            AND network = $4
+        ORDER BY created_at, tx_digest, event_seq
+        LIMIT $2
+        OFFSET $3
+        "#,
+        r#type,
+        limit,
+        offset,
+        network.as_str(),
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(name = "Query nft from database", skip_all)]
+async fn get_nfts_db(
+    pool: &PgPool,
+    args: &NftsArgs,
+    after: Option<&NftCursor>,
+    before: Option<&NftCursor>,
+    limit: i64,
+    backwards: bool,
+) -> StdResult<Vec<Nft>, sqlx::Error> {
+    let columns = sort_columns(args.order_by.field).join(", ");
+    let descending = args.order_by.direction == OrderDirection::Desc;
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT id, type, owner, ownership, url, traits, items, created_at, attached_to, slot
+        FROM nfts
+        "#,
+    );
+    if let (NftOrderField::Trait, Some(name)) = (args.order_by.field, &args.order_by.r#trait) {
+        // Keys can't be null, or the row comparisons of the cursors would be null as well.
+        builder
+            .push(
+                r#"
+                CROSS JOIN LATERAL (
+                    SELECT
+                        number_key IS null, COALESCE(number_key, 0),
+                        text_key IS null, COALESCE(text_key, '')
+                    FROM (
+                        SELECT
+                            CASE WHEN flavour ->> 'type' IN ('number', 'big_number')
+                                THEN (flavour ->> 'value')::numeric
+                            END,
+                            CASE WHEN flavour ->> 'type' IN ('string', 'bool')
+                                THEN flavour ->> 'value'
+                            END
+                        FROM (
+                            SELECT (
+                                SELECT t -> 'flavour' FROM jsonb_array_elements(traits) t
+                                WHERE t ->> 'name' = "#,
+            )
+            .push_bind(name.clone())
+            .push(
+                r#" LIMIT 1
+                            )
+                        ) f(flavour)
+                    ) k(number_key, text_key)
+                ) sort(no_number, number_key, no_text, text_key)
+                "#,
+            );
+    }
+    push_nft_filters(&mut builder, args);
+    let (after_op, before_op) = if descending { ("<", ">") } else { (">", "<") };
+    if let Some(cursor) = after {
+        push_cursor(&mut builder, &columns, after_op, cursor);
+    }
+    if let Some(cursor) = before {
+        push_cursor(&mut builder, &columns, before_op, cursor);
+    }
+    // Backward pages are taken from the end, the caller puts them back in order.
+    let direction = if descending != backwards { " DESC" } else { "" };
+    builder.push(" ORDER BY ");
+    let mut order_by = builder.separated(", ");
+    for column in sort_columns(args.order_by.field) {
+        order_by.push(column).push_unseparated(direction);
+    }
+    builder.push(" LIMIT ").push_bind(limit);
+
+    let ret = builder
+        .build()
+        .try_map(nft_from_row)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|nft| Nft::from(nft).on_network(args.network))
+        .collect();
+
+    Ok(ret)
+}
+
+/// Columns NFTs are sorted by in the order, ending with the id to break ties.
+///
+/// Ordering by a trait sorts on the keys computed in `get_nfts_db`. In ascending order numbers
+/// come first, then strings and bools, then NFTs without the trait, descending order is the
+/// exact reverse. Creation time and owner orders sort on indexed columns, so their pages are
+/// read from the indexes rather than sorted.
+fn sort_columns(field: NftOrderField) -> &'static [&'static str] {
+    match field {
+        NftOrderField::CreatedAt => &["created_at", "id"],
+        NftOrderField::Id => &["id"],
+        NftOrderField::Owner => &["owner", "id"],
+        NftOrderField::ItemCount => &["jsonb_array_length(items)", "id"],
+        NftOrderField::Trait => &["no_number", "number_key", "no_text", "text_key", "id"],
+    }
+}
+
+/// Keeps the NFTs on the `op` side of the cursor.
+fn push_cursor(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    columns: &str,
+    op: &str,
+    cursor: &NftCursor,
+) {
+    builder.push(format_args!(" AND ({columns}) {op} ("));
+    let mut values = builder.separated(", ");
+    match &cursor.key {
+        SortKey::Id => {}
+        SortKey::CreatedAt(created_at) => {
+            values.push_bind(*created_at);
+        }
+        SortKey::Owner(owner) => {
+            values.push_bind(owner.clone());
+        }
+        SortKey::ItemCount(count) => {
+            values.push_bind(*count);
+        }
+        SortKey::Trait { number, text } => {
+            values
+                .push_bind(number.is_none())
+                .push_bind(number.clone().unwrap_or_else(|| "0".to_string()))
+                .push_unseparated("::numeric")
+                .push_bind(text.is_none())
+                .push_bind(text.clone().unwrap_or_default());
+        }
+    }
+    values.push_bind(cursor.id.clone());
+    builder.push(")");
+}
+
+/// Conditions of the `nfts` query on the NFTs, shared by its page and its count.
+fn push_nft_filters(builder: &mut QueryBuilder<'_, Postgres>, args: &NftsArgs) {
+    builder
+        .push(" WHERE network = ")
+        .push_bind(args.network.as_str());
+    if let Some(owner) = &args.owner {
+        builder.push(" AND owner = ").push_bind(owner.clone());
+        if !args.indirect {
+            builder.push(" AND ownership->>'kind' = 'address_owner'");
+        }
+    }
+    if let Some(kind) = args.r#type {
+        builder.push(" AND type = ").push_bind(kind);
+    }
+    if let Some(ids) = &args.filter.ids {
+        builder
+            .push(" AND id = ANY(")
+            .push_bind(ids.clone())
+            .push(")");
+    }
+    if !args.filter.traits.is_empty() {
+        // Containment of the names can use the GIN index, the flavours are checked after.
+        builder
+            .push(
+                r#"
+                AND traits @> (
+                    SELECT jsonb_agg(jsonb_build_object('name', f -> 'name'))
+                    FROM jsonb_array_elements("#,
+            )
+            .push_bind(Json(args.filter.traits.clone()))
+            .push(
+                r#") f
+                )
+                AND NOT EXISTS (
+                    SELECT FROM jsonb_array_elements("#,
+            )
+            .push_bind(Json(args.filter.traits.clone()))
+            .push(
+                r#") f
+                    WHERE NOT EXISTS (
+                        SELECT FROM jsonb_array_elements(traits) t
+                        WHERE t -> 'name' = f -> 'name'
+                            AND (f -> 'flavours' IS null
+                                OR t -> 'flavour' IN (SELECT jsonb_array_elements(f -> 'flavours')))
+                    )
+                )
+                "#,
+            );
+    }
+    if let Some(created_after) = args.filter.created_after {
+        builder.push(" AND created_at > ").push_bind(created_after);
+    }
+    if let Some(created_before) = args.filter.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(attached_to) = &args.filter.attached_to {
+        builder
+            .push(" AND attached_to = ")
+            .push_bind(attached_to.clone());
+    }
+    if args.filter.unattached {
+        builder.push(" AND attached_to IS null");
+    }
+}
+
+fn nft_from_row(row: PgRow) -> StdResult<NftSql, sqlx::Error> {
+    Ok(NftSql {
+        id: row.try_get("id")?,
+        r#type: row.try_get("type")?,
+        owner: row.try_get("owner")?,
+        ownership: row.try_get("ownership")?,
+        url: row.try_get("url")?,
+        traits: row.try_get("traits")?,
+        items: row.try_get("items")?,
+        created_at: row.try_get("created_at")?,
+        attached_to: row.try_get("attached_to")?,
+        slot: row.try_get("slot")?,
+    })
+}
+
+#[tracing::instrument(name = "Count nfts in database", skip(pool))]
+async fn count_nfts_db(pool: &PgPool, args: &NftsArgs) -> StdResult<i64, sqlx::Error> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM nfts");
+    push_nft_filters(&mut builder, args);
+
+    builder.build_query_scalar().fetch_one(pool).await
+}
+
 #[tracing::instrument(name = "Query nft from database", skip(pool))]
-async fn get_nft_db(id: String, pool: &PgPool) -> StdResult<Nft, sqlx::Error> {
+async fn get_nft_db(id: ObjectId, network: Network, pool: &PgPool) -> StdResult<Nft, sqlx::Error> {
     query_as!(
         NftSql,
         r#"
         SELECT 
-            id,
-            type,
-            owner, 
+            id as "id: ObjectId",
+            type as "type: NftKind",
+            owner as "owner: Address", 
+            ownership as "ownership: Json<Ownership>",
             url, 
             traits as "traits: Json<Vec<Trait>>", 
             items as "items: Json<Vec<NftSql>>", 
             created_at,
-            attached_to
+            attached_to as "attached_to: ObjectId",
+            slot as "slot: Slot"
         FROM nfts 
-        WHERE id = $1
+        WHERE id = $1 AND network = $2
         "#,
-        id
+        id.as_str(),
+        network.as_str(),
     )
     .fetch_one(pool)
     .await
-    .map(Into::into)
+    .map(|nft| Nft::from(nft).on_network(network))
 }
 #[derive(Debug)]
 pub struct MutationRoot;
@@ -95,14 +643,20 @@ pub struct MutationRoot;
 #[Object]
 impl MutationRoot {
     #[tracing::instrument(name = "Mutation starting. Inserting NFT", skip(ctx))]
-    async fn insert_nft(&self, ctx: &Context<'_>, nft: Nft) -> Result<bool> {
+    async fn insert_nft(
+        &self,
+        ctx: &Context<'_>,
+        nft: Nft,
+        network: Option<Network>,
+    ) -> Result<bool> {
         let nft = nft.into();
+        let network = network_or_default(ctx, network);
         let pool = ctx.data_unchecked::<PgPool>();
         let mut tx = pool
             .begin()
             .await
             .context("Failed to start SQL transaction")?;
-        insert_nft_db(&nft, &mut tx)
+        insert_nft_db(&nft, network, &mut tx)
             .await
             .context("Failed to insert the nft into database")?;
         tx.commit()
@@ -112,14 +666,152 @@ impl MutationRoot {
         Ok(true)
     }
 
+    #[tracing::instrument(name = "Mutation starting. Upserting NFT", skip(ctx))]
+    async fn upsert_nft(
+        &self,
+        ctx: &Context<'_>,
+        nft: Nft,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let nft = nft.into();
+        let network = network_or_default(ctx, network);
+        let pool = ctx.data_unchecked::<PgPool>();
+        let mut tx = pool
+            .begin()
+            .await
+            .context("Failed to start SQL transaction")?;
+        upsert_nft_db(&nft, network, &mut tx)
+            .await
+            .context("Failed to upsert the nft into database")?;
+        tx.commit()
+            .await
+            .context("Failed to commit SQL transaction to upsert nft")?;
+
+        Ok(true)
+    }
+
+    #[tracing::instrument(name = "Mutation starting. Patching NFT", skip(ctx))]
+    async fn patch_nft(
+        &self,
+        ctx: &Context<'_>,
+        id: ObjectId,
+        patch: NftPatch,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let network = network_or_default(ctx, network);
+        let pool = ctx.data_unchecked::<PgPool>();
+        let mut tx = pool
+            .begin()
+            .await
+            .context("Failed to start SQL transaction")?;
+        patch_nft_db(&id, patch, network, &mut tx)
+            .await
+            .context("Failed to patch the nft in database")?;
+        tx.commit()
+            .await
+            .context("Failed to commit SQL transaction to patch nft")?;
+
+        Ok(true)
+    }
+
+    #[tracing::instrument(name = "Mutation starting. Inserting transaction", skip(ctx))]
+    async fn insert_transaction(
+        &self,
+        ctx: &Context<'_>,
+        transaction: TransactionRecord,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let transaction = transaction.into();
+        let network = network_or_default(ctx, network);
+        let pool = ctx.data_unchecked::<PgPool>();
+        insert_transaction_db(&transaction, network, pool)
+            .await
+            .context("Failed to insert the transaction into database")?;
+
+        Ok(true)
+    }
+
+    /// Store an event the indexer has no handler for, it's a no-op for a stored one.
+    #[tracing::instrument(name = "Mutation starting. Inserting unknown event", skip(ctx))]
+    async fn insert_unknown_event(
+        &self,
+        ctx: &Context<'_>,
+        tx_digest: String,
+        event_seq: i64,
+        event: UnknownEvent,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let network = network_or_default(ctx, network);
+        let pool = ctx.data_unchecked::<PgPool>();
+        insert_unknown_event_db(&tx_digest, event_seq, &event, network, pool)
+            .await
+            .context("Failed to insert the unknown event into database")?;
+
+        Ok(true)
+    }
+
+    /// Drop a stored unknown event once it's re-processed.
+    #[tracing::instrument(name = "Mutation starting. Deleting unknown event", skip(ctx))]
+    async fn delete_unknown_event(
+        &self,
+        ctx: &Context<'_>,
+        tx_digest: String,
+        event_seq: i64,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let network = network_or_default(ctx, network);
+        let pool = ctx.data_unchecked::<PgPool>();
+        delete_unknown_event_db(&tx_digest, event_seq, network, pool)
+            .await
+            .context("Failed to delete the unknown event from database")?;
+
+        Ok(true)
+    }
+
     #[tracing::instrument(name = "Mutation starting. Adding Item to NFT", skip(ctx))]
-    async fn add_item(&self, ctx: &Context<'_>, lemon_id: String, item_id: String) -> Result<bool> {
+    /// Attach the item in `slot`, which defaults to the one of the item's `slot` trait.
+    /// A slot holds one item at most.
+    async fn add_item(
+        &self,
+        ctx: &Context<'_>,
+        lemon_id: ObjectId,
+        item_id: ObjectId,
+        slot: Option<Slot>,
+        network: Option<Network>,
+    ) -> Result<bool> {
+        let network = network_or_default(ctx, network);
         let pool = ctx.data_unchecked::<PgPool>();
         let mut tx = pool
             .begin()
             .await
             .context("Failed to start SQL transaction")?;
-        add_item_db(&lemon_id, &item_id, &mut tx)
+        let lemon_kind = get_nft_kind_db(&lemon_id, network, &mut tx)
+            .await
+            .context("Failed to get the lemon's kind from database")?;
+        let item_kind = get_nft_kind_db(&item_id, network, &mut tx)
+            .await
+            .context("Failed to get the item's kind from database")?;
+        if let (Some(lemon_kind), Some(item_kind)) = (lemon_kind, item_kind) {
+            ensure!(
+                lemon_kind.accepts(item_kind),
+                "NFTs of kind `{item_kind}` can't be attached to NFTs of kind `{lemon_kind}`"
+            );
+        }
+        let slot = match slot {
+            Some(slot) => Some(slot),
+            None => get_item_slot_db(&item_id, network, &mut tx)
+                .await
+                .context("Failed to get the item's traits from database")?,
+        };
+        if let Some(slot) = slot {
+            let taken_by = get_slot_item_db(&lemon_id, slot, network, &mut tx)
+                .await
+                .context("Failed to get the lemon's loadout from database")?;
+            if let Some(taken_by) = taken_by.filter(|taken_by| *taken_by != item_id) {
+                bail!("Slot `{slot}` of lemon `{lemon_id}` is taken by item `{taken_by}`");
+            }
+        }
+        add_item_db(&lemon_id, &item_id, slot, network, &mut tx)
             .await
             .context("Failed to add item to lemon in database")?;
         tx.commit()
@@ -133,15 +825,17 @@ impl MutationRoot {
     async fn remove_item(
         &self,
         ctx: &Context<'_>,
-        lemon_id: String,
-        item_id: String,
+        lemon_id: ObjectId,
+        item_id: ObjectId,
+        network: Option<Network>,
     ) -> Result<bool> {
+        let network = network_or_default(ctx, network);
         let pool = ctx.data_unchecked::<PgPool>();
         let mut tx = pool
             .begin()
             .await
             .context("Failed to start SQL transaction")?;
-        remove_item_db(&lemon_id, &item_id, &mut tx)
+        remove_item_db(&lemon_id, &item_id, network, &mut tx)
             .await
             .context("Failed to add item to lemon in database")?;
         tx.commit()
@@ -153,8 +847,9 @@ impl MutationRoot {
 }
 
 async fn remove_item_db(
-    lemon_id: &str,
-    item_id: &str,
+    lemon_id: &ObjectId,
+    item_id: &ObjectId,
+    network: Network,
     tx: &mut Transaction<'_, Postgres>,
 ) -> StdResult<(), sqlx::Error> {
     query!(
@@ -165,10 +860,11 @@ async fn remove_item_db(
                         FROM jsonb_array_elements(items) elements
                         WHERE elements->> 'id' != $1),
                         '[]'::jsonb)
-        WHERE id = $2
+        WHERE id = $2 AND network = $3
         "#,
-        item_id,
-        lemon_id,
+        item_id.as_str(),
+        lemon_id.as_str(),
+        network.as_str(),
     )
     .execute(&mut *tx)
     .await?;
@@ -176,10 +872,12 @@ async fn remove_item_db(
     query!(
         r#"
         UPDATE nfts
-        SET attached_to = NULL
-        WHERE id = $1
+        SET attached_to = NULL, ownership = jsonb_build_object('kind', 'address_owner', 'address', owner),
+            slot = NULL
+        WHERE id = $1 AND network = $2
         "#,
-        item_id,
+        item_id.as_str(),
+        network.as_str(),
     )
     .execute(&mut *tx)
     .await?;
@@ -187,32 +885,150 @@ async fn remove_item_db(
     Ok(())
 }
 
+#[tracing::instrument(name = "Query nft kind from database", skip(tx))]
+async fn get_nft_kind_db(
+    id: &ObjectId,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<Option<NftKind>, sqlx::Error> {
+    let ret = query!(
+        r#"SELECT type as "type: NftKind" FROM nfts WHERE id = $1 AND network = $2"#,
+        id.as_str(),
+        network.as_str(),
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|row| row.r#type);
+
+    Ok(ret)
+}
+
+/// Slot given by the item's `slot` trait, if it has a known one.
+#[tracing::instrument(name = "Query item slot from database", skip(tx))]
+async fn get_item_slot_db(
+    id: &ObjectId,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<Option<Slot>, sqlx::Error> {
+    let ret = query!(
+        r#"SELECT traits as "traits: Json<Vec<Trait>>" FROM nfts WHERE id = $1 AND network = $2"#,
+        id.as_str(),
+        network.as_str(),
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .and_then(|row| Slot::from_traits(&row.traits));
+
+    Ok(ret)
+}
+
+#[tracing::instrument(name = "Query item in lemon slot from database", skip(tx))]
+async fn get_slot_item_db(
+    lemon_id: &ObjectId,
+    slot: Slot,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<Option<ObjectId>, sqlx::Error> {
+    let ret = query!(
+        r#"
+        SELECT id as "id: ObjectId"
+        FROM nfts
+        WHERE attached_to = $1 AND slot = $2 AND network = $3
+        "#,
+        lemon_id.as_str(),
+        slot as _,
+        network.as_str(),
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .map(|row| row.id);
+
+    Ok(ret)
+}
+
 #[tracing::instrument(name = "Add item to lemon in database", skip(tx))]
 async fn add_item_db(
-    lemon_id: &str,
-    item_id: &str,
+    lemon_id: &ObjectId,
+    item_id: &ObjectId,
+    slot: Option<Slot>,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        r#"
+        UPDATE nfts
+        SET attached_to = $2, ownership = jsonb_build_object('kind', 'object_owner', 'object', $2::text),
+            slot = $4
+        WHERE id = $1 AND network = $3
+        "#,
+        item_id.as_str(),
+        lemon_id.as_str(),
+        network.as_str(),
+        slot as _,
+    )
+    .execute(&mut *tx)
+    .await?;
+    resolve_owner_db(item_id, network, tx).await?;
+
+    // Replayed events attach the same item again, it's listed once.
+    query!(
+        r#"
+        UPDATE nfts
+        SET items = items || (SELECT to_jsonb(r) FROM nfts r WHERE id = $1 AND network = $3)
+        WHERE id = $2 AND network = $3
+            AND NOT items @> jsonb_build_array(jsonb_build_object('id', $1::text));
+        "#,
+        item_id.as_str(),
+        lemon_id.as_str(),
+        network.as_str(),
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Resolve the wallet owning the NFT through its object owner, if any, and hand it down to
+/// the objects the NFT owns in turn.
+#[tracing::instrument(name = "Resolve nft owner in database", skip(tx))]
+async fn resolve_owner_db(
+    id: &ObjectId,
+    network: Network,
     tx: &mut Transaction<'_, Postgres>,
 ) -> StdResult<(), sqlx::Error> {
     query!(
         r#"
         UPDATE nfts
-        SET items = items || (SELECT to_jsonb(r) FROM nfts r WHERE id = $1)
-        WHERE id = $2;
+        SET owner = parent.owner
+        FROM nfts parent
+        WHERE nfts.id = $1 AND nfts.network = $2
+            AND nfts.ownership->>'kind' = 'object_owner'
+            AND parent.id = nfts.ownership->>'object' AND parent.network = $2
         "#,
-        item_id,
-        lemon_id,
+        id.as_str(),
+        network.as_str(),
     )
     .execute(&mut *tx)
     .await?;
 
+    // `UNION` rather than `UNION ALL`, so an ownership cycle doesn't recurse forever.
     query!(
         r#"
+        WITH RECURSIVE descendants (id, owner) AS (
+            SELECT id, owner FROM nfts WHERE id = $1 AND network = $2
+            UNION
+            SELECT child.id, descendants.owner
+            FROM nfts child
+            JOIN descendants ON child.ownership->>'object' = descendants.id
+            WHERE child.network = $2 AND child.ownership->>'kind' = 'object_owner'
+        )
         UPDATE nfts
-        SET attached_to = $2
-        WHERE id = $1
+        SET owner = descendants.owner
+        FROM descendants
+        WHERE nfts.id = descendants.id AND nfts.network = $2 AND nfts.id != $1
         "#,
-        item_id,
-        lemon_id,
+        id.as_str(),
+        network.as_str(),
     )
     .execute(&mut *tx)
     .await?;
@@ -226,31 +1042,210 @@ async fn insert_nft_db(
         id,
         r#type,
         owner,
+        ownership,
         url,
         traits,
         items,
         created_at,
         attached_to,
+        slot,
     }: &NftSql,
+    network: Network,
     tx: &mut Transaction<'_, Postgres>,
 ) -> StdResult<(), sqlx::Error> {
     query!(
         r#"
-        INSERT INTO nfts (id, type, owner, url, traits, created_at, items, attached_to)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        INSERT INTO nfts (id, type, owner, url, traits, created_at, items, attached_to, network, ownership, slot)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
         ON CONFLICT DO NOTHING 
         "#,
+        id.as_str(),
+        r#type as _,
+        owner.as_str(),
+        url,
+        traits as _,
+        created_at,
+        items as _,
+        attached_to as _,
+        network.as_str(),
+        ownership as _,
+        slot as _,
+    )
+    .execute(&mut *tx)
+    .await?;
+    resolve_owner_db(id, network, tx).await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Insert transaction to database", skip(pool))]
+async fn insert_transaction_db(
+    TransactionSql {
+        digest,
+        sender,
+        timestamp,
+        checkpoint,
+        gas_used,
+        status,
+        move_calls,
+        affected_objects,
+    }: &TransactionSql,
+    network: Network,
+    pool: &PgPool,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        r#"
+        INSERT INTO transactions (digest, sender, timestamp, checkpoint, gas_used, status, move_calls, affected_objects, network)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT DO NOTHING
+        "#,
+        digest,
+        sender.as_str(),
+        timestamp,
+        checkpoint as _,
+        gas_used,
+        status,
+        move_calls as _,
+        affected_objects as _,
+        network.as_str(),
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Insert unknown event to database", skip(pool))]
+async fn insert_unknown_event_db(
+    tx_digest: &str,
+    event_seq: i64,
+    UnknownEvent {
+        r#type,
+        sender,
+        fields,
+    }: &UnknownEvent,
+    network: Network,
+    pool: &PgPool,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        r#"
+        INSERT INTO unknown_events (tx_digest, event_seq, type, sender, fields, network)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT DO NOTHING
+        "#,
+        tx_digest,
+        event_seq,
+        r#type as _,
+        sender.as_str(),
+        fields,
+        network.as_str(),
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Delete unknown event from database", skip(pool))]
+async fn delete_unknown_event_db(
+    tx_digest: &str,
+    event_seq: i64,
+    network: Network,
+    pool: &PgPool,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        "DELETE FROM unknown_events WHERE tx_digest = $1 AND event_seq = $2 AND network = $3",
+        tx_digest,
+        event_seq,
+        network.as_str()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Upsert nft to database", skip(tx))]
+async fn upsert_nft_db(
+    NftSql {
         id,
         r#type,
         owner,
+        ownership,
+        url,
+        traits,
+        items,
+        created_at,
+        attached_to,
+        slot,
+    }: &NftSql,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        r#"
+        INSERT INTO nfts (id, type, owner, url, traits, created_at, items, attached_to, network, ownership, slot)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (network, id) DO UPDATE
+        SET type = EXCLUDED.type, owner = EXCLUDED.owner, url = EXCLUDED.url, traits = EXCLUDED.traits,
+            ownership = CASE WHEN nfts.attached_to IS null THEN EXCLUDED.ownership ELSE nfts.ownership END
+        "#,
+        id.as_str(),
+        r#type as _,
+        owner.as_str(),
         url,
         traits as _,
         created_at,
         items as _,
         attached_to as _,
+        network.as_str(),
+        ownership as _,
+        slot as _,
+    )
+    .execute(&mut *tx)
+    .await?;
+    resolve_owner_db(id, network, tx).await?;
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Patch nft in database", skip(tx))]
+async fn patch_nft_db(
+    id: &ObjectId,
+    NftPatch {
+        r#type,
+        owner,
+        url,
+        traits,
+    }: NftPatch,
+    network: Network,
+    tx: &mut Transaction<'_, Postgres>,
+) -> StdResult<(), sqlx::Error> {
+    query!(
+        r#"
+        UPDATE nfts
+        SET
+            type = COALESCE($2, type),
+            owner = COALESCE($3, owner),
+            ownership = CASE
+                WHEN $3 IS NOT null AND ownership->>'kind' = 'address_owner'
+                    THEN jsonb_build_object('kind', 'address_owner', 'address', $3::text)
+                ELSE ownership
+            END,
+            url = COALESCE($4, url),
+            traits = COALESCE($5, traits)
+        WHERE id = $1 AND network = $6
+        "#,
+        id.as_str(),
+        r#type as _,
+        owner as _,
+        url,
+        traits.map(Json) as _,
+        network.as_str(),
     )
     .execute(&mut *tx)
     .await?;
+    resolve_owner_db(id, network, tx).await?;
 
     Ok(())
 }
@@ -261,42 +1256,55 @@ async fn update_nft_db(
         id,
         r#type,
         owner,
+        ownership,
         url,
         traits,
         items,
         created_at,
         attached_to,
+        slot,
     }: &NftSql,
+    network: Network,
     tx: &mut Transaction<'_, Postgres>,
 ) -> StdResult<(), sqlx::Error> {
     query!(
         r#"
         UPDATE nfts
-        SET type = $2, owner = $3, url = $4, traits = $5, items = $6, attached_to = $7
-        WHERE id = $1
+        SET type = $2, owner = $3, url = $4, traits = $5, items = $6, attached_to = $7, ownership = $9,
+            slot = $10
+        WHERE id = $1 AND network = $8
         "#,
-        id,
-        r#type,
-        owner,
+        id.as_str(),
+        r#type as _,
+        owner.as_str(),
         url,
         traits as _,
         items as _,
         attached_to as _,
+        network.as_str(),
+        ownership as _,
+        slot as _,
     )
     .execute(&mut *tx)
     .await?;
+    resolve_owner_db(id, network, tx).await?;
 
     Ok(())
 }
 
 #[tracing::instrument(name = "Delete nft from database", skip(tx))]
 async fn delete_nft_db(
-    id: String,
+    id: ObjectId,
+    network: Network,
     tx: &mut Transaction<'_, Postgres>,
 ) -> StdResult<(), sqlx::Error> {
-    query!("DELETE FROM nfts WHERE id = $1", id)
-        .execute(&mut *tx)
-        .await?;
+    query!(
+        "DELETE FROM nfts WHERE id = $1 AND network = $2",
+        id.as_str(),
+        network.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
 
     Ok(())
 }
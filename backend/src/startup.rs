@@ -6,6 +6,7 @@ use async_graphql::{EmptySubscription, Schema};
 use axum::routing::IntoMakeService;
 use axum::{Router, Server};
 use hyper::server::conn::AddrIncoming;
+use models::Network;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::net::TcpListener;
@@ -30,7 +31,7 @@ impl App {
         let port = listener.local_addr()?.port();
 
         info!("Compose GraphQL Schema");
-        let graphql_schema = build_graphql_schema(&db_pool);
+        let graphql_schema = build_graphql_schema(&db_pool, config.graphql.default_network);
         let server = setup_server(listener, db_pool, graphql_schema, config)
             .context("Failed to setup server")?;
 
@@ -72,9 +73,10 @@ pub fn get_db_pool(config: &DatabaseConfig) -> PgPool {
         .connect_lazy_with(config.with_db())
 }
 
-pub fn build_graphql_schema(db_pool: &PgPool) -> BattlemonSchema {
+pub fn build_graphql_schema(db_pool: &PgPool, default_network: Network) -> BattlemonSchema {
     let db_pool = db_pool.clone();
     Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(db_pool)
+        .data(default_network)
         .finish()
 }
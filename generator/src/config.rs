@@ -0,0 +1,106 @@
+use eyre::{Context, Result};
+use indexer::config::{BackendConfig, ScriptingConfig};
+use models::events::{EventDecoder, ParseMode};
+use models::Network;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Config {
+    /// Runs with the same seed and config produce the same events.
+    pub seed: u64,
+    pub rate_per_sec: f64,
+    /// Generate events forever when it isn't set.
+    pub total_events: Option<u64>,
+    pub package: String,
+    #[serde(default = "default_wallets")]
+    pub wallets: usize,
+    #[serde(default = "default_max_items_per_lemon")]
+    pub max_items_per_lemon: usize,
+    pub weights: EventWeights,
+    #[serde(default)]
+    pub lemon_traits: Vec<TraitDistribution>,
+    #[serde(default)]
+    pub item_traits: Vec<TraitDistribution>,
+    pub sink: SinkConfig,
+}
+
+fn default_wallets() -> usize {
+    10
+}
+
+fn default_max_items_per_lemon() -> usize {
+    5
+}
+
+/// Relative frequencies of generated event kinds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct EventWeights {
+    pub lemon_created: u32,
+    pub item_created: u32,
+    pub item_added: u32,
+    pub item_removed: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TraitDistribution {
+    pub name: String,
+    pub flavours: Vec<FlavourWeight>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FlavourWeight {
+    pub flavour: String,
+    pub weight: u32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// Print event envelopes to stdout as JSON lines.
+    Stdout,
+    /// Run envelopes through the indexer's event handling, as if they came from a Sui node.
+    Indexer {
+        backend: BackendConfig,
+        #[serde(default = "default_network")]
+        network: Network,
+        #[serde(default)]
+        scripting: ScriptingConfig,
+        #[serde(default)]
+        dry_run: bool,
+        #[serde(default)]
+        parse_mode: ParseMode,
+        #[serde(default)]
+        event_decoder: EventDecoder,
+    },
+    /// Parse envelopes into models and send them straight to the backend.
+    Backend {
+        backend: BackendConfig,
+        #[serde(default = "default_network")]
+        network: Network,
+    },
+}
+
+/// Generated events don't come from a real network, so they are tagged as local ones.
+fn default_network() -> Network {
+    Network::Localnet
+}
+
+pub fn load_config() -> Result<Config> {
+    let config_path = std::env::current_dir()
+        .context("Failed to determine the current directory")?
+        .join("config");
+
+    let config = config::Config::builder()
+        .add_source(config::File::from(config_path.join("generator.toml")))
+        .add_source(
+            config::Environment::with_prefix("APP")
+                .prefix_separator("_")
+                .separator("__"),
+        )
+        .build()
+        .context("Failed to build config")?;
+
+    config
+        .try_deserialize()
+        .context("Failed to deserialize config files into `Config`")
+}
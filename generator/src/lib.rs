@@ -0,0 +1,412 @@
+use crate::config::{Config, EventWeights, SinkConfig, TraitDistribution};
+use eyre::{ensure, Context, Result};
+use indexer::config::BackendConfig;
+use indexer::dry_run::DryRun;
+use indexer::scripting::Scripts;
+use indexer::{handle_contract_event, send_event};
+use models::events::{EventRegistry, ParseMode};
+use models::move_events::encode_bcs;
+use models::sui_sdk::rpc_types::{
+    EventID, SuiEvent, SuiEventEnvelope, SuiMoveStruct, SuiMoveValue,
+};
+use models::sui_sdk::types::base_types::{
+    ObjectID, SuiAddress, TransactionDigest, SUI_ADDRESS_LENGTH,
+};
+use models::sui_sdk::types::Identifier;
+use models::{KindSpec, Network, NftKind, Slot};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+pub mod config;
+
+/// Module declaring the `Trait` struct and the events attaching items.
+const LEMON_MODULE: &str = NftKind::Lemon.spec().module;
+/// Timestamps of generated events start from 2023-01-01, so they don't depend on the clock.
+const START_TIMESTAMP_MS: u64 = 1_672_531_200_000;
+
+#[derive(Debug, Clone, Copy)]
+enum EventKind {
+    LemonCreated,
+    ItemCreated,
+    ItemAdded,
+    ItemRemoved,
+}
+
+const EVENT_KINDS: [EventKind; 4] = [
+    EventKind::LemonCreated,
+    EventKind::ItemCreated,
+    EventKind::ItemAdded,
+    EventKind::ItemRemoved,
+];
+
+struct Lemon {
+    id: SuiAddress,
+    owner: SuiAddress,
+    items: Vec<GeneratedItem>,
+}
+
+impl Lemon {
+    /// Whether the item fits, a lemon holds at most one item per slot.
+    fn fits(&self, slot: Option<Slot>) -> bool {
+        slot.map_or(true, |slot| {
+            self.items.iter().all(|item| item.slot != Some(slot))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GeneratedItem {
+    id: SuiAddress,
+    /// Slot given by the item's `slot` trait.
+    slot: Option<Slot>,
+}
+
+/// Deterministic generator of contract's event envelopes.
+///
+/// It keeps track of created lemons and items, so attach and detach events always refer to
+/// NFTs which exist and are in the right state.
+pub struct Generator {
+    rng: ChaCha8Rng,
+    package: ObjectID,
+    wallets: Vec<SuiAddress>,
+    kinds: WeightedIndex<u32>,
+    lemon_traits: Vec<(String, Vec<String>, WeightedIndex<u32>)>,
+    item_traits: Vec<(String, Vec<String>, WeightedIndex<u32>)>,
+    max_items_per_lemon: usize,
+    lemons: Vec<Lemon>,
+    free_items: Vec<GeneratedItem>,
+    tx_seq: i64,
+    timestamp_ms: u64,
+    step_ms: u64,
+}
+
+impl Generator {
+    pub fn new(config: &Config) -> Result<Self> {
+        ensure!(config.rate_per_sec > 0.0, "Rate must be positive");
+        ensure!(config.wallets > 0, "At least one wallet is required");
+
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        let package = ObjectID::from_hex_literal(&config.package)
+            .context("Failed to parse package address")?;
+        let wallets = (0..config.wallets)
+            .map(|_| random_address(&mut rng))
+            .collect();
+        let EventWeights {
+            lemon_created,
+            item_created,
+            item_added,
+            item_removed,
+        } = config.weights;
+        let kinds = WeightedIndex::new([lemon_created, item_created, item_added, item_removed])
+            .context("Invalid event weights")?;
+
+        Ok(Self {
+            rng,
+            package,
+            wallets,
+            kinds,
+            lemon_traits: trait_distributions(&config.lemon_traits)?,
+            item_traits: trait_distributions(&config.item_traits)?,
+            max_items_per_lemon: config.max_items_per_lemon,
+            lemons: Vec::new(),
+            free_items: Vec::new(),
+            tx_seq: 0,
+            timestamp_ms: START_TIMESTAMP_MS,
+            step_ms: (1000.0 / config.rate_per_sec).max(1.0) as u64,
+        })
+    }
+
+    /// Package the generated events are emitted by.
+    pub fn package(&self) -> ObjectID {
+        self.package
+    }
+
+    pub fn next_envelope(&mut self) -> SuiEventEnvelope {
+        let kind = EVENT_KINDS[self.kinds.sample(&mut self.rng)];
+        let (sender, module, name, fields) = match kind {
+            EventKind::ItemAdded if self.can_attach() => self.item_added(),
+            EventKind::ItemRemoved if self.can_detach() => self.item_removed(),
+            EventKind::ItemCreated | EventKind::ItemAdded | EventKind::ItemRemoved
+                if !self.lemons.is_empty() =>
+            {
+                self.item_created()
+            }
+            _ => self.lemon_created(),
+        };
+
+        self.tx_seq += 1;
+        self.timestamp_ms += self.step_ms;
+        let mut digest = [0; 32];
+        self.rng.fill_bytes(&mut digest);
+        let bcs = encode_bcs(name, fields.clone()).expect("generated fields match the Move types");
+
+        SuiEventEnvelope {
+            timestamp: self.timestamp_ms,
+            tx_digest: TransactionDigest::new(digest),
+            id: EventID {
+                tx_seq: self.tx_seq,
+                event_seq: 0,
+            },
+            event: SuiEvent::MoveEvent {
+                package_id: self.package,
+                transaction_module: Identifier::new(module).unwrap(),
+                sender,
+                type_: format!("{}::{module}::{name}", self.package),
+                fields: Some(SuiMoveStruct::WithFields(fields)),
+                bcs,
+            },
+        }
+    }
+
+    fn can_attach(&self) -> bool {
+        let slots = self.free_slots();
+        self.lemons
+            .iter()
+            .any(|lemon| self.accepts_any(lemon, &slots))
+    }
+
+    /// Slots of the free items, `None` stands for the items without one.
+    fn free_slots(&self) -> HashSet<Option<Slot>> {
+        self.free_items.iter().map(|item| item.slot).collect()
+    }
+
+    /// Whether the lemon has room for one of the free items with the given slots.
+    fn accepts_any(&self, lemon: &Lemon, slots: &HashSet<Option<Slot>>) -> bool {
+        lemon.items.len() < self.max_items_per_lemon && slots.iter().any(|&slot| lemon.fits(slot))
+    }
+
+    fn can_detach(&self) -> bool {
+        self.lemons.iter().any(|lemon| !lemon.items.is_empty())
+    }
+
+    fn lemon_created(&mut self) -> (SuiAddress, &'static str, &'static str, Fields) {
+        let id = random_address(&mut self.rng);
+        let owner = *self.wallets.choose(&mut self.rng).unwrap();
+        let (fields, _) = self.nft_fields(id, NftKind::Lemon);
+        self.lemons.push(Lemon {
+            id,
+            owner,
+            items: Vec::new(),
+        });
+
+        let KindSpec {
+            module,
+            created_event,
+            ..
+        } = NftKind::Lemon.spec();
+        (owner, module, created_event, fields)
+    }
+
+    fn item_created(&mut self) -> (SuiAddress, &'static str, &'static str, Fields) {
+        let id = random_address(&mut self.rng);
+        let owner = *self.wallets.choose(&mut self.rng).unwrap();
+        let (fields, slot) = self.nft_fields(id, NftKind::Item);
+        self.free_items.push(GeneratedItem { id, slot });
+
+        let KindSpec {
+            module,
+            created_event,
+            ..
+        } = NftKind::Item.spec();
+        (owner, module, created_event, fields)
+    }
+
+    fn item_added(&mut self) -> (SuiAddress, &'static str, &'static str, Fields) {
+        let slots = self.free_slots();
+        let lemons: Vec<usize> = (0..self.lemons.len())
+            .filter(|&idx| self.accepts_any(&self.lemons[idx], &slots))
+            .collect();
+        let lemon = &mut self.lemons[*lemons.choose(&mut self.rng).unwrap()];
+        let items: Vec<usize> = (0..self.free_items.len())
+            .filter(|&idx| lemon.fits(self.free_items[idx].slot))
+            .collect();
+        let item = self
+            .free_items
+            .swap_remove(*items.choose(&mut self.rng).unwrap());
+        lemon.items.push(item);
+
+        let fields = attachment_fields(lemon.id, item.id);
+        (lemon.owner, LEMON_MODULE, "ItemAdded", fields)
+    }
+
+    fn item_removed(&mut self) -> (SuiAddress, &'static str, &'static str, Fields) {
+        let lemons: Vec<usize> = (0..self.lemons.len())
+            .filter(|&idx| !self.lemons[idx].items.is_empty())
+            .collect();
+        let lemon = &mut self.lemons[*lemons.choose(&mut self.rng).unwrap()];
+        let item = self.rng.gen_range(0..lemon.items.len());
+        let item = lemon.items.swap_remove(item);
+        self.free_items.push(item);
+
+        let fields = attachment_fields(lemon.id, item.id);
+        (lemon.owner, LEMON_MODULE, "ItemRemoved", fields)
+    }
+
+    /// Fields of the created event and the slot picked for the `slot` trait, if any.
+    fn nft_fields(&mut self, id: SuiAddress, kind: NftKind) -> (Fields, Option<Slot>) {
+        let distributions = match kind {
+            NftKind::Lemon => &self.lemon_traits,
+            NftKind::Item => &self.item_traits,
+        };
+        let trait_type = format!("{}::{LEMON_MODULE}::Trait", self.package);
+        let mut slot = None;
+        let traits = distributions
+            .iter()
+            .map(|(name, flavours, weights)| {
+                let flavour = &flavours[weights.sample(&mut self.rng)];
+                if name == Slot::TRAIT {
+                    slot = flavour.parse().ok();
+                }
+                let fields = BTreeMap::from([
+                    ("name".to_string(), SuiMoveValue::String(name.clone())),
+                    ("flavour".to_string(), SuiMoveValue::String(flavour.clone())),
+                ]);
+                SuiMoveValue::Struct(SuiMoveStruct::WithTypes {
+                    type_: trait_type.clone(),
+                    fields,
+                })
+            })
+            .collect();
+        let url = format!("https://battlemon.com/nfts/{id}.png");
+
+        let fields = BTreeMap::from([
+            ("id".to_string(), SuiMoveValue::Address(id)),
+            ("url".to_string(), SuiMoveValue::String(url)),
+            ("traits".to_string(), SuiMoveValue::Vector(traits)),
+        ]);
+        (fields, slot)
+    }
+}
+
+type Fields = BTreeMap<String, SuiMoveValue>;
+
+fn attachment_fields(lemon_id: SuiAddress, item_id: SuiAddress) -> Fields {
+    BTreeMap::from([
+        ("lemon_id".to_string(), SuiMoveValue::Address(lemon_id)),
+        ("item_id".to_string(), SuiMoveValue::Address(item_id)),
+    ])
+}
+
+fn random_address(rng: &mut ChaCha8Rng) -> SuiAddress {
+    let mut bytes = [0; SUI_ADDRESS_LENGTH];
+    rng.fill_bytes(&mut bytes);
+    SuiAddress::try_from(bytes.as_slice()).unwrap()
+}
+
+fn trait_distributions(
+    distributions: &[TraitDistribution],
+) -> Result<Vec<(String, Vec<String>, WeightedIndex<u32>)>> {
+    distributions
+        .iter()
+        .map(|TraitDistribution { name, flavours }| {
+            let weights = WeightedIndex::new(flavours.iter().map(|f| f.weight))
+                .with_context(|| format!("Invalid flavour weights of trait `{name}`"))?;
+            let flavours = flavours.iter().map(|f| f.flavour.clone()).collect();
+            Ok((name.clone(), flavours, weights))
+        })
+        .collect()
+}
+
+/// Destination of generated events.
+pub enum Sink {
+    Stdout,
+    Indexer {
+        backend: BackendConfig,
+        network: Network,
+        scripts: Scripts,
+        registry: EventRegistry,
+        parse_mode: ParseMode,
+        dry_run: Option<DryRun>,
+    },
+    Backend {
+        backend: BackendConfig,
+        network: Network,
+        registry: EventRegistry,
+    },
+}
+
+impl Sink {
+    /// The sinks which parse events only accept the ones of `package`.
+    pub fn build(config: SinkConfig, package: ObjectID) -> Result<Self> {
+        let sink = match config {
+            SinkConfig::Stdout => Sink::Stdout,
+            SinkConfig::Indexer {
+                backend,
+                network,
+                scripting,
+                dry_run,
+                parse_mode,
+                event_decoder,
+            } => Sink::Indexer {
+                backend,
+                network,
+                scripts: Scripts::load(&scripting.scripts, scripting.max_operations)
+                    .context("Failed to load event scripts")?,
+                registry: EventRegistry::contract([package.into()]).with_decoder(event_decoder),
+                parse_mode,
+                dry_run: dry_run.then(DryRun::new),
+            },
+            SinkConfig::Backend { backend, network } => Sink::Backend {
+                backend,
+                network,
+                registry: EventRegistry::contract([package.into()]),
+            },
+        };
+
+        Ok(sink)
+    }
+
+    pub async fn send(&self, envelope: SuiEventEnvelope) -> Result<()> {
+        match self {
+            Sink::Stdout => {
+                let line = serde_json::to_string(&envelope)?;
+                writeln!(std::io::stdout().lock(), "{line}")?;
+            }
+            Sink::Indexer {
+                backend,
+                network,
+                scripts,
+                registry,
+                parse_mode,
+                dry_run,
+            } => {
+                handle_contract_event(
+                    &envelope,
+                    backend,
+                    *network,
+                    scripts,
+                    registry,
+                    *parse_mode,
+                    dry_run.as_ref(),
+                )
+                .await?;
+            }
+            Sink::Backend {
+                backend,
+                network,
+                registry,
+            } => {
+                let parsed = registry
+                    .parse(&envelope, ParseMode::Lenient)
+                    .context("Failed to convert `SuiEvent` into `Event`")?;
+                send_event(&envelope, parsed.event, backend, *network).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(&self) {
+        if let Sink::Indexer {
+            dry_run: Some(dry_run),
+            ..
+        } = self
+        {
+            dry_run.print_summary();
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use eyre::{Result, WrapErr};
+use generator::{config, Generator, Sink};
+use indexer::telemetry;
+use std::time::Duration;
+use tracing::{error, info};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Stdout may be used by the sink, so logs go to stderr.
+    let subscriber = telemetry::get_subscriber("generator".into(), "info".into(), std::io::stderr);
+    telemetry::init_subscriber(subscriber).wrap_err("Failed to init tracing subscriber")?;
+    info!("Loading application config");
+    let config = config::load_config().wrap_err("Failed to load app config")?;
+    let mut generator = Generator::new(&config).wrap_err("Failed to setup generator")?;
+    let sink =
+        Sink::build(config.sink.clone(), generator.package()).wrap_err("Failed to setup sink")?;
+
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / config.rate_per_sec));
+    let mut sent = 0;
+    info!("Start to generate events with seed {}", config.seed);
+    while config.total_events.map_or(true, |total| sent < total) {
+        interval.tick().await;
+        let envelope = generator.next_envelope();
+        if let Err(err) = sink.send(envelope).await {
+            error!("Failed to send generated event. Error: {err:?}");
+        }
+        sent += 1;
+    }
+
+    sink.finish();
+    info!("Generated {sent} events");
+
+    Ok(())
+}
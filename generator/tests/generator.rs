@@ -0,0 +1,44 @@
+use generator::config::Config;
+use generator::Generator;
+use serde_json::{json, Value};
+
+const PACKAGE: &str = "0x9b5b3f8e3fd1c6aaf2e8f5a4e1f5bd2c1d2e7f40";
+
+fn config(seed: u64) -> Config {
+    serde_json::from_value(json!({
+        "seed": seed,
+        "rate_per_sec": 10.0,
+        "package": PACKAGE,
+        "weights": {
+            "lemon_created": 3,
+            "item_created": 5,
+            "item_added": 4,
+            "item_removed": 1,
+        },
+        "item_traits": [{
+            "name": "slot",
+            "flavours": [
+                { "flavour": "head", "weight": 1 },
+                { "flavour": "weapon", "weight": 1 },
+                { "flavour": "back", "weight": 1 },
+            ],
+        }],
+        "sink": { "kind": "stdout" },
+    }))
+    .expect("Invalid generator config")
+}
+
+fn generate(seed: u64, count: usize) -> Vec<Value> {
+    let mut generator = Generator::new(&config(seed)).expect("Failed to setup generator");
+    (0..count)
+        .map(|_| serde_json::to_value(generator.next_envelope()).unwrap())
+        .collect()
+}
+
+#[test]
+fn same_seed_generates_same_events() {
+    let events = generate(42, 200);
+
+    assert_eq!(events, generate(42, 200));
+    assert_ne!(events, generate(43, 200));
+}
@@ -0,0 +1,85 @@
+use crate::control::{Control, RewindTarget, Status};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post, IntoMakeService};
+use axum::{Json, Router, Server};
+use hyper::server::conn::AddrIncoming;
+use models::sui_sdk::types::base_types::TransactionDigest;
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub type AdminServer = Server<AddrIncoming, IntoMakeService<Router>>;
+
+type AdminResult<T> = Result<T, (StatusCode, String)>;
+
+#[rustfmt::skip]
+#[tracing::instrument(name = "Setup admin server", skip_all)]
+pub fn setup_admin_server(listener: TcpListener, control: Arc<Control>) -> eyre::Result<AdminServer> {
+    let router = Router::new()
+        .route("/status", get(status))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/rewind", post(rewind))
+        .route("/transactions/:digest/reprocess", post(reprocess))
+        .route("/unknown-events/reprocess", post(reprocess_unknown))
+        .with_state(control);
+
+    Ok(axum::Server::from_tcp(listener)?.serve(router.into_make_service()))
+}
+
+#[tracing::instrument(name = "Admin: status", skip_all)]
+async fn status(State(control): State<Arc<Control>>) -> Json<Status> {
+    Json(control.status())
+}
+
+#[tracing::instrument(name = "Admin: pause", skip_all)]
+async fn pause(State(control): State<Arc<Control>>) -> Json<Status> {
+    control.pause();
+    Json(control.status())
+}
+
+#[tracing::instrument(name = "Admin: resume", skip_all)]
+async fn resume(State(control): State<Arc<Control>>) -> Json<Status> {
+    control.resume();
+    Json(control.status())
+}
+
+#[tracing::instrument(name = "Admin: rewind", skip(control))]
+async fn rewind(
+    State(control): State<Arc<Control>>,
+    Json(target): Json<RewindTarget>,
+) -> AdminResult<StatusCode> {
+    control
+        .rewind(target)
+        .await
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("{e:?}")))?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[tracing::instrument(name = "Admin: reprocess transaction", skip(control))]
+async fn reprocess(
+    State(control): State<Arc<Control>>,
+    Path(digest): Path<String>,
+) -> AdminResult<Json<Value>> {
+    let digest = TransactionDigest::from_str(&digest)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid digest: {e}")))?;
+    let events = control
+        .reprocess(digest)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+
+    Ok(Json(json!({ "events": events })))
+}
+
+#[tracing::instrument(name = "Admin: reprocess unknown events", skip(control))]
+async fn reprocess_unknown(State(control): State<Arc<Control>>) -> AdminResult<Json<Value>> {
+    let events = control
+        .reprocess_unknown()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")))?;
+
+    Ok(Json(json!({ "events": events })))
+}
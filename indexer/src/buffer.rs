@@ -0,0 +1,257 @@
+use crate::config::BufferConfig;
+use eyre::{bail, Context, Result};
+use models::sui_sdk::rpc_types::SuiEventEnvelope;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+const GAP_KEY: &[u8] = b"gap";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QueuedEvent {
+    pub endpoint: usize,
+    pub envelope: SuiEventEnvelope,
+}
+
+/// What the delivery loop should do next.
+#[derive(Debug)]
+pub enum Delivery {
+    Event {
+        seq: u64,
+        event: QueuedEvent,
+    },
+    /// Events received since the timestamp were dropped because the buffer was full,
+    /// so they have to be fetched from the node again.
+    Backfill {
+        since_ms: u64,
+    },
+}
+
+/// Events received since `since_ms` were dropped and have to be backfilled.
+///
+/// Events are dropped until the backfill starts, then they're queued again and the ones
+/// from `held_from` on are delivered after the backfill.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+struct Gap {
+    since_ms: u64,
+    held_from: Option<u64>,
+    dropping: bool,
+}
+
+/// Embedded on-disk queue of received events, drained in order by the delivery loop.
+///
+/// An event is flushed to disk before it's acknowledged, so neither a backend outage nor
+/// a restart loses it. Once the queue outgrows `max_bytes` new events are dropped and
+/// the timestamp of the first dropped one is persisted; they are backfilled from the node
+/// after the events queued before them are delivered.
+pub struct EventBuffer {
+    db: sled::Db,
+    queue: sled::Tree,
+    meta: sled::Tree,
+    next_seq: AtomicU64,
+    bytes: AtomicU64,
+    max_bytes: u64,
+    alert_bytes: u64,
+    alerting: AtomicBool,
+    gap: Mutex<Option<Gap>>,
+    pushed: Notify,
+}
+
+impl EventBuffer {
+    #[tracing::instrument(name = "Opening events buffer", skip_all)]
+    pub fn open(config: &BufferConfig) -> Result<Self> {
+        let db = sled::open(&config.path)
+            .with_context(|| format!("Failed to open `{}`", config.path.display()))?;
+        let queue = db.open_tree("queue")?;
+        let meta = db.open_tree("meta")?;
+
+        let next_seq = match queue.last()? {
+            Some((key, _)) => decode_u64(&key)? + 1,
+            None => 0,
+        };
+        let mut bytes = 0;
+        for entry in queue.iter() {
+            let (_, value) = entry?;
+            bytes += value.len() as u64;
+        }
+        let gap = match meta.get(GAP_KEY)? {
+            Some(value) => Some(serde_json::from_slice(&value).context("Corrupted buffer gap")?),
+            None => None,
+        };
+        info!(
+            "Buffer has {} queued events ({bytes} bytes) left from the previous run",
+            queue.len()
+        );
+
+        Ok(Self {
+            db,
+            queue,
+            meta,
+            next_seq: AtomicU64::new(next_seq),
+            bytes: AtomicU64::new(bytes),
+            max_bytes: config.max_bytes,
+            alert_bytes: (config.max_bytes as f64 * config.alert_ratio) as u64,
+            alerting: AtomicBool::new(false),
+            gap: Mutex::new(gap),
+            pushed: Notify::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn has_gap(&self) -> bool {
+        self.gap.lock().unwrap().is_some()
+    }
+
+    /// Durably append the event to the queue.
+    ///
+    /// Returns `false` if the event was dropped, because the buffer is full or there is
+    /// a gap whose backfill hasn't started yet.
+    pub async fn push(&self, endpoint: usize, envelope: SuiEventEnvelope) -> Result<bool> {
+        let gap = *self.gap.lock().unwrap();
+        if gap.map_or(false, |gap| gap.dropping) {
+            return Ok(false);
+        }
+
+        let (timestamp, tx_digest) = (envelope.timestamp, envelope.tx_digest.to_string());
+        let value = serde_json::to_vec(&QueuedEvent { endpoint, envelope })?;
+        let size = value.len() as u64;
+        let bytes = self.bytes() + size;
+        if bytes > self.max_bytes {
+            error!(
+                "Events buffer is full ({} of {} bytes), dropping events since tx `{tx_digest}` until it's drained",
+                self.bytes(),
+                self.max_bytes,
+            );
+            // Dropping again during a backfill widens its gap, so it's replayed once more.
+            let gap = match gap {
+                Some(gap) => Gap {
+                    dropping: true,
+                    ..gap
+                },
+                None => Gap {
+                    since_ms: timestamp,
+                    held_from: None,
+                    dropping: true,
+                },
+            };
+            self.set_gap(Some(gap)).await?;
+            self.pushed.notify_one();
+            return Ok(false);
+        }
+
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.queue.insert(seq.to_be_bytes(), value)?;
+        self.db
+            .flush_async()
+            .await
+            .context("Failed to flush events buffer")?;
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+
+        if bytes > self.alert_bytes && !self.alerting.swap(true, Ordering::Relaxed) {
+            warn!(
+                "Events buffer is above the alert threshold ({bytes} of {} bytes)",
+                self.max_bytes
+            );
+        }
+        self.pushed.notify_one();
+
+        Ok(true)
+    }
+
+    /// Wait for the next thing to deliver, the oldest queued event goes first and events
+    /// queued after a gap wait for its backfill.
+    pub async fn next(&self) -> Result<Delivery> {
+        loop {
+            let gap = *self.gap.lock().unwrap();
+            if let Some((key, value)) = self.queue.first()? {
+                let seq = decode_u64(&key)?;
+                let held = gap
+                    .and_then(|gap| gap.held_from)
+                    .map_or(false, |held_from| seq >= held_from);
+                if !held {
+                    let event =
+                        serde_json::from_slice(&value).context("Corrupted buffered event")?;
+                    return Ok(Delivery::Event { seq, event });
+                }
+            }
+            if let Some(gap) = gap {
+                return Ok(Delivery::Backfill {
+                    since_ms: gap.since_ms,
+                });
+            }
+            self.pushed.notified().await;
+        }
+    }
+
+    /// Remove the delivered event from the queue.
+    pub async fn remove(&self, seq: u64) -> Result<()> {
+        if let Some(value) = self.queue.remove(seq.to_be_bytes())? {
+            self.bytes.fetch_sub(value.len() as u64, Ordering::Relaxed);
+        }
+        self.db.flush_async().await?;
+
+        if self.bytes() <= self.alert_bytes && self.alerting.swap(false, Ordering::Relaxed) {
+            info!("Events buffer is back below the alert threshold");
+        }
+
+        Ok(())
+    }
+
+    /// Queue events again before the gap is backfilled, so the ones received meanwhile
+    /// aren't dropped. The backfill has to cover everything emitted before this call.
+    pub async fn resume_after_gap(&self) -> Result<()> {
+        let gap = *self.gap.lock().unwrap();
+        if let Some(gap) = gap.filter(|gap| gap.dropping) {
+            let held_from = self.next_seq.load(Ordering::Relaxed);
+            self.set_gap(Some(Gap {
+                since_ms: gap.since_ms,
+                held_from: gap.held_from.or(Some(held_from)),
+                dropping: false,
+            }))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Forget the gap once it's backfilled, unless events were dropped again meanwhile.
+    pub async fn close_gap(&self) -> Result<()> {
+        let gap = *self.gap.lock().unwrap();
+        if gap.map_or(false, |gap| !gap.dropping) {
+            self.set_gap(None).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_gap(&self, gap: Option<Gap>) -> Result<()> {
+        match gap {
+            Some(gap) => self.meta.insert(GAP_KEY, serde_json::to_vec(&gap)?)?,
+            None => self.meta.remove(GAP_KEY)?,
+        };
+        self.db.flush_async().await?;
+        *self.gap.lock().unwrap() = gap;
+
+        Ok(())
+    }
+}
+
+fn decode_u64(bytes: &[u8]) -> Result<u64> {
+    match <[u8; 8]>::try_from(bytes) {
+        Ok(bytes) => Ok(u64::from_be_bytes(bytes)),
+        Err(_) => bail!("Corrupted events buffer entry"),
+    }
+}
@@ -0,0 +1,67 @@
+use crate::history::History;
+use crate::rpc::RpcPool;
+use eyre::{eyre, Context, Result};
+use models::sui_sdk::rpc_types::{EventQuery, SuiEventEnvelope};
+use models::sui_sdk::types::base_types::TransactionDigest;
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+
+#[derive(Deserialize, Debug)]
+struct CheckpointContents {
+    transactions: Vec<ExecutionDigests>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExecutionDigests {
+    transaction: String,
+}
+
+/// Events of a checkpoint in execution order, with the endpoints which served them.
+#[derive(Debug)]
+pub struct Checkpoint {
+    pub sequence_number: u64,
+    pub events: Vec<(usize, SuiEventEnvelope)>,
+}
+
+pub async fn latest_checkpoint(rpc: &RpcPool) -> Result<u64> {
+    let (_, latest) = rpc
+        .call("sui_getLatestCheckpointSequenceNumber", json!([]))
+        .await
+        .context("Failed to fetch the latest checkpoint")?;
+
+    Ok(latest)
+}
+
+/// Fetch the checkpoint's transactions and collect their events.
+///
+/// Events keep the ids the node gave them, the checkpoint's sequence number is kept apart.
+#[tracing::instrument(name = "Fetching checkpoint", skip(rpc))]
+pub async fn fetch_checkpoint(rpc: &RpcPool, sequence_number: u64) -> Result<Checkpoint> {
+    let (_, contents): (_, CheckpointContents) = rpc
+        .call(
+            "sui_getCheckpointContentsBySequenceNumber",
+            json!([sequence_number]),
+        )
+        .await
+        .context("Failed to fetch checkpoint contents")?;
+
+    let mut events = Vec::new();
+    for ExecutionDigests { transaction } in contents.transactions {
+        let digest = TransactionDigest::from_str(&transaction)
+            .map_err(|e| eyre!("Invalid transaction digest `{transaction}`: {e}"))?;
+        let mut history = History::new(rpc, vec![EventQuery::Transaction(digest)], None);
+        while let Some(event) = history
+            .next()
+            .await
+            .with_context(|| format!("Failed to fetch events of transaction `{transaction}`"))?
+        {
+            events.push(event);
+        }
+    }
+
+    Ok(Checkpoint {
+        sequence_number,
+        events,
+    })
+}
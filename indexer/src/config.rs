@@ -1,16 +1,84 @@
 use eyre::{anyhow, Context, Result};
+use models::events::{EventDecoder, ParseMode};
+use models::{Network, ObjectId};
 use serde::Deserialize;
+use sqlx::postgres::PgConnectOptions;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Config {
+    /// Network of the Sui nodes, every indexed row is tagged with it.
+    pub network: Network,
     pub sui_json_rpc: SuiJsonRpcConfig,
     pub sui_contract: SuiContractConfig,
     pub backend: BackendConfig,
+    /// The admin API is disabled when it isn't configured.
+    pub admin: Option<AdminConfig>,
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+    /// Print mutations to stdout instead of sending them to the backend.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Whether events with parse diagnostics are rejected or indexed as far as possible.
+    #[serde(default)]
+    pub parse_mode: ParseMode,
+    /// Whether events are decoded from their JSON fields, their BCS payload or both.
+    #[serde(default)]
+    pub event_decoder: EventDecoder,
+    /// Replicas coordinate through Postgres when it's set, otherwise the indexer runs alone.
+    pub ha: Option<HaConfig>,
+    /// Events are queued on disk until the backend accepts them when it's set.
+    pub buffer: Option<BufferConfig>,
+    #[serde(default)]
+    pub ingestion: IngestionConfig,
+}
+
+/// How events get from the Sui node to the indexer.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum IngestionConfig {
+    /// Subscribe to the contract's events, fetching missed ones after reconnects.
+    #[default]
+    Subscription,
+    /// Walk checkpoints one by one, which is gap-free and totally ordered.
+    ///
+    /// The last indexed checkpoint is stored with the `ha` cursor, or in `checkpoint_file`
+    /// without `ha`, so a restart resumes after it instead of starting over.
+    Checkpoints {
+        #[serde(default)]
+        start_checkpoint: u64,
+        #[serde(default = "default_poll_interval_ms")]
+        poll_interval_ms: u64,
+        #[serde(default = "default_checkpoint_file")]
+        checkpoint_file: PathBuf,
+    },
+}
+
+fn default_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_checkpoint_file() -> PathBuf {
+    PathBuf::from("indexed_checkpoint")
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct SuiJsonRpcConfig {
+    pub endpoints: Vec<SuiRpcEndpointConfig>,
+    /// Confirm every event against a second endpoint before applying it.
+    #[serde(default)]
+    pub verify_events: bool,
+    #[serde(default = "default_failover_delay_secs")]
+    pub failover_delay_secs: u64,
+}
+
+fn default_failover_delay_secs() -> u64 {
+    5
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SuiRpcEndpointConfig {
     pub http_url: String,
     pub ws_url: String,
 }
@@ -18,6 +86,10 @@ pub struct SuiJsonRpcConfig {
 #[derive(Deserialize, Clone, Debug)]
 pub struct SuiContractConfig {
     pub address: String,
+    /// Packages whose events are handled besides `address`, e.g. the original package of an
+    /// upgraded contract, as its events keep the original package in their type.
+    #[serde(default)]
+    pub additional_packages: Vec<ObjectId>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -26,6 +98,122 @@ pub struct BackendConfig {
     pub port: u16,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct AdminConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptingConfig {
+    #[serde(default)]
+    pub scripts: Vec<ScriptConfig>,
+    /// Upper bound of operations a single script run may perform.
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            scripts: Vec::new(),
+            max_operations: default_max_operations(),
+        }
+    }
+}
+
+fn default_max_operations() -> u64 {
+    100_000
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptConfig {
+    /// Fully qualified Move event type, e.g. `0x2::devnet_nft::MintNFTEvent`.
+    pub event_type: String,
+    pub path: PathBuf,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct HaConfig {
+    pub db: DatabaseConfig,
+    /// Key of the advisory lock, replicas indexing the same contract must share it.
+    pub lock_id: i64,
+    /// Name of the shared cursor, defaults to the lock key.
+    pub cursor_name: Option<String>,
+    #[serde(default = "default_retry_interval_secs")]
+    pub retry_interval_secs: u64,
+    #[serde(default = "default_heartbeat_interval_secs")]
+    pub heartbeat_interval_secs: u64,
+}
+
+impl HaConfig {
+    pub fn cursor_name(&self) -> String {
+        self.cursor_name
+            .clone()
+            .unwrap_or_else(|| self.lock_id.to_string())
+    }
+}
+
+fn default_retry_interval_secs() -> u64 {
+    1
+}
+
+fn default_heartbeat_interval_secs() -> u64 {
+    2
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BufferConfig {
+    /// Directory of the embedded queue, it survives restarts.
+    pub path: PathBuf,
+    /// Size of queued events above which new ones are dropped and later backfilled.
+    #[serde(default = "default_buffer_max_bytes")]
+    pub max_bytes: u64,
+    /// Share of `max_bytes` above which an alert is logged.
+    #[serde(default = "default_buffer_alert_ratio")]
+    pub alert_ratio: f64,
+    #[serde(default = "default_retry_interval_secs")]
+    pub retry_interval_secs: u64,
+    /// Delivery retries back off exponentially up to this interval.
+    #[serde(default = "default_max_retry_interval_secs")]
+    pub max_retry_interval_secs: u64,
+}
+
+fn default_buffer_max_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_buffer_alert_ratio() -> f64 {
+    0.8
+}
+
+fn default_max_retry_interval_secs() -> u64 {
+    60
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DatabaseConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub db_name: String,
+}
+
+impl DatabaseConfig {
+    pub fn without_db(&self) -> PgConnectOptions {
+        PgConnectOptions::new()
+            .host(&self.host)
+            .username(&self.username)
+            .password(&self.password)
+            .port(self.port)
+    }
+
+    pub fn with_db(&self) -> PgConnectOptions {
+        self.without_db().database(&self.db_name)
+    }
+}
+
 impl BackendConfig {
     pub fn graphql_url(&self) -> String {
         format!("http://{}:{}/graphql", self.host, self.port)
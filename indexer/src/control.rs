@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Result};
+use models::sui_sdk::rpc_types::{EventID, SuiEventEnvelope};
+use models::sui_sdk::types::base_types::TransactionDigest;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// Snapshot of the indexer's progress exposed by the admin API.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Status {
+    pub paused: bool,
+    pub cursor: Option<EventID>,
+    /// Last fully indexed checkpoint in the checkpoint ingestion mode.
+    pub checkpoint: Option<u64>,
+    pub last_event_timestamp_ms: Option<u64>,
+    pub lag_ms: Option<u64>,
+    pub buffered_events: usize,
+    /// Events waiting in the on-disk buffer for the backend.
+    pub queued_events: usize,
+    pub queued_bytes: u64,
+    /// Some events were dropped by the full on-disk buffer and wait to be backfilled.
+    pub backfill_pending: bool,
+    pub processed_events: u64,
+    pub failed_events: u64,
+    /// Problems found while parsing events which were indexed anyway.
+    pub parse_diagnostics: u64,
+    /// Transactions which couldn't be recorded, their events were applied anyway.
+    pub unrecorded_transactions: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RewindTarget {
+    Cursor(EventID),
+    Timestamp(DateTime<Utc>),
+    /// Index again starting from the checkpoint, only in the checkpoint ingestion mode.
+    Checkpoint(u64),
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Rewind(RewindTarget),
+    Reprocess {
+        digest: TransactionDigest,
+        reply: oneshot::Sender<Result<usize>>,
+    },
+    ReprocessUnknown {
+        reply: oneshot::Sender<Result<usize>>,
+    },
+}
+
+/// State shared between the events pipeline and the admin API.
+pub struct Control {
+    paused: watch::Sender<bool>,
+    status: Mutex<Status>,
+    commands: mpsc::Sender<Command>,
+}
+
+impl Control {
+    pub fn new() -> (Self, mpsc::Receiver<Command>) {
+        let (paused, _) = watch::channel(false);
+        let (commands, commands_rx) = mpsc::channel(16);
+        let control = Self {
+            paused,
+            status: Mutex::new(Status::default()),
+            commands,
+        };
+
+        (control, commands_rx)
+    }
+
+    pub fn status(&self) -> Status {
+        let mut status = self.status.lock().unwrap().clone();
+        status.paused = *self.paused.borrow();
+        status.lag_ms = status
+            .last_event_timestamp_ms
+            .map(|ts| (Utc::now().timestamp_millis() as u64).saturating_sub(ts));
+        status
+    }
+
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn subscribe_paused(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+
+    pub fn event_buffered(&self) {
+        self.status.lock().unwrap().buffered_events += 1;
+    }
+
+    pub fn event_unbuffered(&self) {
+        let mut status = self.status.lock().unwrap();
+        status.buffered_events = status.buffered_events.saturating_sub(1);
+    }
+
+    pub fn queue_changed(&self, events: usize, bytes: u64, backfill_pending: bool) {
+        let mut status = self.status.lock().unwrap();
+        status.queued_events = events;
+        status.queued_bytes = bytes;
+        status.backfill_pending = backfill_pending;
+    }
+
+    pub fn event_processed(&self, envelope: &SuiEventEnvelope, success: bool) {
+        let mut status = self.status.lock().unwrap();
+        status.cursor = Some(envelope.id.clone());
+        status.last_event_timestamp_ms = Some(envelope.timestamp);
+        if success {
+            status.processed_events += 1;
+        } else {
+            status.failed_events += 1;
+        }
+    }
+
+    pub fn diagnostics_reported(&self, count: usize) {
+        self.status.lock().unwrap().parse_diagnostics += count as u64;
+    }
+
+    pub fn transaction_unrecorded(&self) {
+        self.status.lock().unwrap().unrecorded_transactions += 1;
+    }
+
+    pub fn restore_cursor(&self, cursor: EventID) {
+        self.status.lock().unwrap().cursor = Some(cursor);
+    }
+
+    pub fn checkpoint_indexed(&self, checkpoint: Option<u64>) {
+        self.status.lock().unwrap().checkpoint = checkpoint;
+    }
+
+    pub async fn rewind(&self, target: RewindTarget) -> Result<()> {
+        self.commands
+            .send(Command::Rewind(target))
+            .await
+            .map_err(|_| eyre!("Events pipeline is stopped"))
+    }
+
+    /// Re-apply all contract events of the transaction and return how many were applied.
+    pub async fn reprocess(&self, digest: TransactionDigest) -> Result<usize> {
+        let (reply, reply_rx) = oneshot::channel();
+        self.commands
+            .send(Command::Reprocess { digest, reply })
+            .await
+            .map_err(|_| eyre!("Events pipeline is stopped"))?;
+
+        reply_rx
+            .await
+            .map_err(|_| eyre!("Events pipeline dropped the request"))?
+    }
+
+    /// Re-apply stored unknown events which got a handler since, return how many there were.
+    pub async fn reprocess_unknown(&self) -> Result<usize> {
+        let (reply, reply_rx) = oneshot::channel();
+        self.commands
+            .send(Command::ReprocessUnknown { reply })
+            .await
+            .map_err(|_| eyre!("Events pipeline is stopped"))?;
+
+        reply_rx
+            .await
+            .map_err(|_| eyre!("Events pipeline dropped the request"))?
+    }
+}
@@ -0,0 +1,132 @@
+use models::sui_sdk::rpc_types::EventID;
+use sqlx::{query, PgPool};
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+
+/// Last applied event, or checkpoint in the checkpoint ingestion mode, shared by all indexer
+/// replicas, so a new leader resumes where the previous one stopped.
+pub struct CursorStore {
+    pool: PgPool,
+    name: String,
+}
+
+impl CursorStore {
+    pub fn new(pool: PgPool, name: String) -> Self {
+        Self { pool, name }
+    }
+
+    #[tracing::instrument(name = "Load cursor from database", skip(self))]
+    pub async fn load(&self) -> Result<Option<EventID>, sqlx::Error> {
+        let ret = query!(
+            r#"
+            SELECT tx_seq, event_seq
+            FROM indexer_cursors
+            WHERE name = $1
+            "#,
+            self.name,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .and_then(|row| match (row.tx_seq, row.event_seq) {
+            (Some(tx_seq), Some(event_seq)) => Some(EventID { tx_seq, event_seq }),
+            _ => None,
+        });
+
+        Ok(ret)
+    }
+
+    #[tracing::instrument(name = "Load checkpoint from database", skip(self))]
+    pub async fn load_checkpoint(&self) -> Result<Option<u64>, sqlx::Error> {
+        let ret = query!(
+            r#"
+            SELECT checkpoint
+            FROM indexer_cursors
+            WHERE name = $1
+            "#,
+            self.name,
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .and_then(|row| row.checkpoint)
+        .map(|checkpoint| checkpoint as u64);
+
+        Ok(ret)
+    }
+
+    #[tracing::instrument(name = "Save checkpoint to database", skip(self))]
+    pub async fn save_checkpoint(&self, checkpoint: u64) -> Result<(), sqlx::Error> {
+        query!(
+            r#"
+            INSERT INTO indexer_cursors (name, checkpoint, updated_at)
+            VALUES ($1, $2, now())
+            ON CONFLICT (name) DO UPDATE
+            SET checkpoint = EXCLUDED.checkpoint, updated_at = EXCLUDED.updated_at
+            "#,
+            self.name,
+            checkpoint as i64,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Save cursor to database", skip(self))]
+    pub async fn save(&self, cursor: &EventID) -> Result<(), sqlx::Error> {
+        query!(
+            r#"
+            INSERT INTO indexer_cursors (name, tx_seq, event_seq, updated_at)
+            VALUES ($1, $2, $3, now())
+            ON CONFLICT (name) DO UPDATE
+            SET tx_seq = EXCLUDED.tx_seq, event_seq = EXCLUDED.event_seq, updated_at = EXCLUDED.updated_at
+            "#,
+            self.name,
+            cursor.tx_seq,
+            cursor.event_seq,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Last indexed checkpoint of an indexer running without `ha`.
+pub struct CheckpointFile {
+    path: PathBuf,
+}
+
+impl CheckpointFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    #[tracing::instrument(name = "Load checkpoint from file", skip(self))]
+    pub async fn load(&self) -> io::Result<Option<u64>> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let checkpoint = contents
+            .trim()
+            .parse()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+        Ok(Some(checkpoint))
+    }
+
+    /// The checkpoint is written aside and renamed over the file, so a crash doesn't
+    /// leave it truncated.
+    #[tracing::instrument(name = "Save checkpoint to file", skip(self))]
+    pub async fn save(&self, checkpoint: u64) -> io::Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, checkpoint.to_string()).await?;
+        tokio::fs::rename(&tmp, &self.path).await
+    }
+}
+
+/// Events are totally ordered by their transaction and then by their position in it.
+pub fn is_after(id: &EventID, cursor: &EventID) -> bool {
+    (id.tx_seq, id.event_seq) > (cursor.tx_seq, cursor.event_seq)
+}
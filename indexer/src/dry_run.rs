@@ -0,0 +1,88 @@
+use models::events::Diagnostic;
+use models::sui_sdk::rpc_types::{SuiEvent, SuiEventEnvelope};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Prints mutations and parse errors to stdout as JSON lines instead of sending them to the
+/// backend, and keeps counts for the final summary.
+#[derive(Default)]
+pub struct DryRun {
+    summary: Mutex<Summary>,
+}
+
+#[derive(Serialize, Default, Debug)]
+struct Summary {
+    events: BTreeMap<String, u64>,
+    errors: BTreeMap<String, u64>,
+    diagnostics: u64,
+}
+
+impl DryRun {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_queries(
+        &self,
+        envelope: &SuiEventEnvelope,
+        queries: &[Value],
+        diagnostics: &[Diagnostic],
+    ) {
+        let event_type = event_type(&envelope.event);
+        for query in queries {
+            print_line(json!({
+                "kind": "mutation",
+                "tx_digest": envelope.tx_digest.to_string(),
+                "event_id": envelope.id,
+                "event_type": event_type,
+                "query": query,
+                "diagnostics": diagnostics,
+            }));
+        }
+
+        let mut summary = self.summary.lock().unwrap();
+        *summary.events.entry(event_type).or_default() += 1;
+        summary.diagnostics += diagnostics.len() as u64;
+    }
+
+    pub fn record_error(&self, envelope: &SuiEventEnvelope, err: &eyre::Report) {
+        let event_type = event_type(&envelope.event);
+        let error = err.root_cause().to_string();
+        print_line(json!({
+            "kind": "error",
+            "tx_digest": envelope.tx_digest.to_string(),
+            "event_id": envelope.id,
+            "event_type": event_type,
+            "error": format!("{err:#}"),
+        }));
+
+        let mut summary = self.summary.lock().unwrap();
+        *summary.events.entry(event_type).or_default() += 1;
+        *summary.errors.entry(error).or_default() += 1;
+    }
+
+    pub fn print_summary(&self) {
+        let summary = self.summary.lock().unwrap();
+        print_line(json!({
+            "kind": "summary",
+            "events": summary.events,
+            "errors": summary.errors,
+            "diagnostics": summary.diagnostics,
+        }));
+    }
+}
+
+fn event_type(event: &SuiEvent) -> String {
+    match event {
+        SuiEvent::MoveEvent { type_, .. } => type_.clone(),
+        other => other.get_event_type(),
+    }
+}
+
+fn print_line(line: Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{line}");
+}
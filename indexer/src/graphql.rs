@@ -1,4 +1,7 @@
-use models::{Nft, Trait};
+use models::{
+    Address, MoveCall, Network, Nft, NftKind, NftPatch, ObjectId, Ownership, Slot, Trait,
+    TraitValue, TransactionRecord, TransactionStatus, UnknownEvent,
+};
 
 pub mod schema {
     cynic::use_schema!("schema.graphql");
@@ -6,11 +9,15 @@ pub mod schema {
 
 type DateTime = chrono::DateTime<chrono::Utc>;
 cynic::impl_scalar!(DateTime, schema::DateTime);
+cynic::impl_scalar!(Address, schema::Address);
+cynic::impl_scalar!(ObjectId, schema::ObjectId);
+type Json = serde_json::Value;
+cynic::impl_scalar!(Json, schema::JSON);
 
 #[cynic::schema_for_derives(file = "schema.graphql")]
 pub mod insert_nft {
     use super::schema;
-    use super::DateTime;
+    use super::{Address, DateTime, ObjectId};
 
     #[derive(cynic::QueryFragment, Debug)]
     #[cynic(
@@ -22,69 +29,137 @@ pub mod insert_nft {
             id: $id,
             type: $r#type,
             owner: $owner,
+            ownership: $ownership,
             url: $url,
             createdAt: $created_at,
             traits: $traits,
             items: $items,
             attachedTo: $attached_to,
-        })]
+            slot: $slot,
+        }, network: $network)]
         pub insert_nft: bool,
     }
 
     #[derive(cynic::QueryVariables, Debug)]
     pub struct InsertNftMutationArguments {
-        pub id: String,
+        pub id: ObjectId,
         #[cynic(rename = "type")]
-        pub r#type: String,
-        pub owner: String,
+        pub r#type: NftKind,
+        pub owner: Address,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub ownership: Option<OwnershipInput>,
         pub url: String,
         pub traits: Vec<TraitInput>,
         pub items: Vec<NftInput>,
         pub created_at: DateTime,
-        pub attached_to: Option<String>,
+        pub attached_to: Option<ObjectId>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub slot: Option<Slot>,
+        pub network: Option<Network>,
     }
 
     #[derive(cynic::InputObject, Debug)]
     pub struct NftInput {
-        pub id: String,
+        pub id: ObjectId,
         #[cynic(rename = "type")]
-        pub r#type: String,
-        pub owner: String,
+        pub r#type: NftKind,
+        pub owner: Address,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub ownership: Option<OwnershipInput>,
         pub url: String,
         pub traits: Vec<TraitInput>,
         pub items: Vec<NftInput>,
         pub created_at: DateTime,
-        pub attached_to: Option<String>,
+        pub attached_to: Option<ObjectId>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub slot: Option<Slot>,
+    }
+
+    /// One-of input, only the field matching the ownership's kind is sent.
+    #[derive(cynic::InputObject, Debug, Default)]
+    pub struct OwnershipInput {
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub address_owner: Option<Address>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub object_owner: Option<ObjectId>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub shared: Option<i64>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub immutable: Option<bool>,
     }
 
     #[derive(cynic::InputObject, Debug)]
     pub struct TraitInput {
         pub name: String,
-        pub flavour: String,
+        pub flavour: TraitValueInput,
+    }
+
+    /// One-of input, only the field matching the value's type is sent.
+    #[derive(cynic::InputObject, Debug, Default)]
+    pub struct TraitValueInput {
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub string: Option<String>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub number: Option<i64>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub big_number: Option<String>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub bool: Option<bool>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub list: Option<Vec<TraitValueInput>>,
+        #[cynic(skip_serializing_if = "Option::is_none")]
+        pub nested: Option<Vec<TraitInput>>,
+    }
+
+    #[derive(cynic::Enum, Clone, Copy, Debug)]
+    pub enum Network {
+        Localnet,
+        Devnet,
+        Testnet,
+        Mainnet,
+    }
+
+    #[derive(cynic::Enum, Clone, Copy, Debug)]
+    pub enum NftKind {
+        Lemon,
+        Item,
+    }
+
+    #[derive(cynic::Enum, Clone, Copy, Debug)]
+    pub enum Slot {
+        Head,
+        Weapon,
+        Back,
     }
 }
 
 #[cynic::schema_for_derives(file = "schema.graphql")]
 pub mod add_item {
+    use super::insert_nft::{Network, Slot};
     use super::schema;
+    use super::ObjectId;
 
     #[derive(cynic::QueryFragment, Debug)]
     #[cynic(variables = "AddItemMutationArguments", graphql_type = "MutationRoot")]
     pub struct AddItemMutation {
-        #[arguments(lemonId: $lemon_id, itemId: $item_id)]
+        #[arguments(lemonId: $lemon_id, itemId: $item_id, slot: $slot, network: $network)]
         pub add_item: bool,
     }
 
     #[derive(cynic::QueryVariables, Debug)]
     pub struct AddItemMutationArguments {
-        pub lemon_id: String,
-        pub item_id: String,
+        pub lemon_id: ObjectId,
+        pub item_id: ObjectId,
+        pub slot: Option<Slot>,
+        pub network: Option<Network>,
     }
 }
 
 #[cynic::schema_for_derives(file = "schema.graphql")]
 pub mod remove_item {
+    use super::insert_nft::Network;
     use super::schema;
+    use super::ObjectId;
 
     #[derive(cynic::QueryFragment, Debug)]
     #[cynic(
@@ -92,19 +167,295 @@ pub mod remove_item {
         graphql_type = "MutationRoot"
     )]
     pub struct RemoveItemMutation {
-        #[arguments(lemonId: $lemon_id, itemId: $item_id)]
+        #[arguments(lemonId: $lemon_id, itemId: $item_id, network: $network)]
         pub remove_item: bool,
     }
 
     #[derive(cynic::QueryVariables, Debug)]
     pub struct RemoveItemMutationArguments {
-        pub lemon_id: String,
-        pub item_id: String,
+        pub lemon_id: ObjectId,
+        pub item_id: ObjectId,
+        pub network: Option<Network>,
+    }
+}
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod upsert_nft {
+    use super::insert_nft::{Network, NftInput};
+    use super::schema;
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        variables = "UpsertNftMutationArguments",
+        graphql_type = "MutationRoot"
+    )]
+    pub struct UpsertNftMutation {
+        #[arguments(nft: $nft, network: $network)]
+        pub upsert_nft: bool,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct UpsertNftMutationArguments {
+        pub nft: NftInput,
+        pub network: Option<Network>,
     }
 }
+
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod patch_nft {
+    use super::insert_nft::{Network, NftKind, TraitInput};
+    use super::schema;
+    use super::{Address, ObjectId};
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(variables = "PatchNftMutationArguments", graphql_type = "MutationRoot")]
+    pub struct PatchNftMutation {
+        #[arguments(id: $id, patch: $patch, network: $network)]
+        pub patch_nft: bool,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct PatchNftMutationArguments {
+        pub id: ObjectId,
+        pub patch: NftPatchInput,
+        pub network: Option<Network>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    pub struct NftPatchInput {
+        #[cynic(rename = "type")]
+        pub r#type: Option<NftKind>,
+        pub owner: Option<Address>,
+        pub url: Option<String>,
+        pub traits: Option<Vec<TraitInput>>,
+    }
+}
+
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod insert_transaction {
+    use super::insert_nft::Network;
+    use super::schema;
+    use super::{Address, DateTime, ObjectId};
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        variables = "InsertTransactionMutationArguments",
+        graphql_type = "MutationRoot"
+    )]
+    pub struct InsertTransactionMutation {
+        #[arguments(transaction: $transaction, network: $network)]
+        pub insert_transaction: bool,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct InsertTransactionMutationArguments {
+        pub transaction: TransactionInput,
+        pub network: Option<Network>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    pub struct TransactionInput {
+        pub digest: String,
+        pub sender: Address,
+        pub timestamp: DateTime,
+        pub checkpoint: Option<i64>,
+        pub gas_used: i64,
+        pub status: TransactionStatus,
+        pub move_calls: Vec<MoveCallInput>,
+        pub affected_objects: Vec<ObjectId>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    pub struct MoveCallInput {
+        pub package: String,
+        pub module: String,
+        pub function: String,
+    }
+
+    #[derive(cynic::Enum, Clone, Copy, Debug)]
+    pub enum TransactionStatus {
+        Success,
+        Failure,
+    }
+}
+
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod insert_unknown_event {
+    use super::insert_nft::Network;
+    use super::schema;
+    use super::{Address, Json};
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        variables = "InsertUnknownEventMutationArguments",
+        graphql_type = "MutationRoot"
+    )]
+    pub struct InsertUnknownEventMutation {
+        #[arguments(txDigest: $tx_digest, eventSeq: $event_seq, event: $event, network: $network)]
+        pub insert_unknown_event: bool,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct InsertUnknownEventMutationArguments {
+        pub tx_digest: String,
+        pub event_seq: i64,
+        pub event: UnknownEventInput,
+        pub network: Option<Network>,
+    }
+
+    #[derive(cynic::InputObject, Debug)]
+    pub struct UnknownEventInput {
+        #[cynic(rename = "type")]
+        pub r#type: String,
+        pub sender: Address,
+        pub fields: Json,
+    }
+}
+
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod delete_unknown_event {
+    use super::insert_nft::Network;
+    use super::schema;
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(
+        variables = "DeleteUnknownEventMutationArguments",
+        graphql_type = "MutationRoot"
+    )]
+    pub struct DeleteUnknownEventMutation {
+        #[arguments(txDigest: $tx_digest, eventSeq: $event_seq, network: $network)]
+        pub delete_unknown_event: bool,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct DeleteUnknownEventMutationArguments {
+        pub tx_digest: String,
+        pub event_seq: i64,
+        pub network: Option<Network>,
+    }
+}
+
+#[cynic::schema_for_derives(file = "schema.graphql")]
+pub mod unknown_events {
+    use super::insert_nft::Network;
+    use super::schema;
+
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(variables = "UnknownEventsQueryArguments", graphql_type = "QueryRoot")]
+    pub struct UnknownEventsQuery {
+        #[arguments(network: $network, limit: $limit, offset: $offset)]
+        pub unknown_events: Vec<UnknownEventKey>,
+    }
+
+    #[derive(cynic::QueryVariables, Debug)]
+    pub struct UnknownEventsQueryArguments {
+        pub network: Option<Network>,
+        pub limit: i64,
+        pub offset: i64,
+    }
+
+    /// Identity and type of a stored unknown event.
+    #[derive(cynic::QueryFragment, Debug)]
+    #[cynic(graphql_type = "UnknownEventRecord")]
+    pub struct UnknownEventKey {
+        pub tx_digest: String,
+        pub event_seq: i64,
+        #[cynic(rename = "type")]
+        pub r#type: String,
+    }
+}
+
+impl From<Network> for insert_nft::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Localnet => Self::Localnet,
+            Network::Devnet => Self::Devnet,
+            Network::Testnet => Self::Testnet,
+            Network::Mainnet => Self::Mainnet,
+        }
+    }
+}
+
+impl From<NftKind> for insert_nft::NftKind {
+    fn from(kind: NftKind) -> Self {
+        match kind {
+            NftKind::Lemon => Self::Lemon,
+            NftKind::Item => Self::Item,
+        }
+    }
+}
+
+impl From<Slot> for insert_nft::Slot {
+    fn from(slot: Slot) -> Self {
+        match slot {
+            Slot::Head => Self::Head,
+            Slot::Weapon => Self::Weapon,
+            Slot::Back => Self::Back,
+        }
+    }
+}
+
 impl From<Trait> for insert_nft::TraitInput {
     fn from(Trait { name, flavour }: Trait) -> Self {
-        Self { name, flavour }
+        Self {
+            name,
+            flavour: flavour.into(),
+        }
+    }
+}
+
+impl From<TraitValue> for insert_nft::TraitValueInput {
+    fn from(value: TraitValue) -> Self {
+        match value {
+            TraitValue::String(value) => Self {
+                string: Some(value),
+                ..Default::default()
+            },
+            TraitValue::Number(value) => Self {
+                number: Some(value),
+                ..Default::default()
+            },
+            TraitValue::BigNumber(value) => Self {
+                big_number: Some(value),
+                ..Default::default()
+            },
+            TraitValue::Bool(value) => Self {
+                bool: Some(value),
+                ..Default::default()
+            },
+            TraitValue::List(values) => Self {
+                list: Some(values.into_iter().map(Into::into).collect()),
+                ..Default::default()
+            },
+            TraitValue::Nested(traits) => Self {
+                nested: Some(traits.into_iter().map(Into::into).collect()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<Ownership> for insert_nft::OwnershipInput {
+    fn from(ownership: Ownership) -> Self {
+        match ownership {
+            Ownership::AddressOwner { address } => Self {
+                address_owner: Some(address),
+                ..Default::default()
+            },
+            Ownership::ObjectOwner { object } => Self {
+                object_owner: Some(object),
+                ..Default::default()
+            },
+            Ownership::Shared {
+                initial_shared_version,
+            } => Self {
+                shared: Some(initial_shared_version),
+                ..Default::default()
+            },
+            Ownership::Immutable => Self {
+                immutable: Some(true),
+                ..Default::default()
+            },
+        }
     }
 }
 
@@ -112,24 +463,109 @@ impl From<Nft> for insert_nft::NftInput {
     fn from(
         Nft {
             id,
+            network: _,
             r#type,
             owner,
+            ownership,
             url,
             traits,
             items,
             created_at,
             attached_to,
+            slot,
         }: Nft,
     ) -> Self {
         Self {
             id,
-            r#type,
+            r#type: r#type.into(),
             owner,
+            ownership: ownership.map(Into::into),
             url,
             traits: traits.into_iter().map(Into::into).collect(),
             items: items.into_iter().map(Into::into).collect(),
             created_at,
             attached_to,
+            slot: slot.map(Into::into),
+        }
+    }
+}
+
+impl From<NftPatch> for patch_nft::NftPatchInput {
+    fn from(
+        NftPatch {
+            r#type,
+            owner,
+            url,
+            traits,
+        }: NftPatch,
+    ) -> Self {
+        Self {
+            r#type: r#type.map(Into::into),
+            owner,
+            url,
+            traits: traits.map(|traits| traits.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl From<TransactionRecord> for insert_transaction::TransactionInput {
+    fn from(
+        TransactionRecord {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls,
+            affected_objects,
+        }: TransactionRecord,
+    ) -> Self {
+        let status = match status {
+            TransactionStatus::Success => insert_transaction::TransactionStatus::Success,
+            TransactionStatus::Failure => insert_transaction::TransactionStatus::Failure,
+        };
+        Self {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls: move_calls.into_iter().map(Into::into).collect(),
+            affected_objects,
+        }
+    }
+}
+
+impl From<UnknownEvent> for insert_unknown_event::UnknownEventInput {
+    fn from(
+        UnknownEvent {
+            r#type,
+            sender,
+            fields,
+        }: UnknownEvent,
+    ) -> Self {
+        Self {
+            r#type,
+            sender,
+            fields,
+        }
+    }
+}
+
+impl From<MoveCall> for insert_transaction::MoveCallInput {
+    fn from(
+        MoveCall {
+            package,
+            module,
+            function,
+        }: MoveCall,
+    ) -> Self {
+        Self {
+            package,
+            module,
+            function,
         }
     }
 }
@@ -0,0 +1,100 @@
+use crate::rpc::RpcPool;
+use eyre::{Context, Result};
+use models::sui_sdk::rpc_types::{EventID, EventQuery, SuiEventEnvelope};
+use models::sui_sdk::types::base_types::ObjectID;
+use models::NftKind;
+use std::collections::{BTreeSet, VecDeque};
+
+const PAGE_SIZE: usize = 100;
+
+/// Queries of the contract's events, one per module emitting them, as the node can't
+/// query events by package.
+pub fn contract_queries(package: ObjectID) -> Vec<EventQuery> {
+    NftKind::ALL
+        .iter()
+        .map(|kind| kind.spec().module)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|module| EventQuery::MoveModule {
+            package,
+            module: module.to_string(),
+        })
+        .collect()
+}
+
+/// Historical events after the cursor matching any of the queries, fetched page by page
+/// and merged into one stream ordered by event id.
+pub struct History<'a> {
+    rpc: &'a RpcPool,
+    pages: Vec<Pages>,
+}
+
+struct Pages {
+    query: EventQuery,
+    cursor: Option<EventID>,
+    data: VecDeque<(usize, SuiEventEnvelope)>,
+    done: bool,
+}
+
+impl<'a> History<'a> {
+    pub fn new(rpc: &'a RpcPool, queries: Vec<EventQuery>, cursor: Option<EventID>) -> Self {
+        let pages = queries
+            .into_iter()
+            .map(|query| Pages {
+                query,
+                cursor: cursor.clone(),
+                data: VecDeque::new(),
+                done: false,
+            })
+            .collect();
+
+        Self { rpc, pages }
+    }
+
+    /// Next event along with the endpoint which served it, `None` once all are fetched.
+    pub async fn next(&mut self) -> Result<Option<(usize, SuiEventEnvelope)>> {
+        for pages in &mut self.pages {
+            pages.fill(self.rpc).await?;
+        }
+
+        let next = self
+            .pages
+            .iter_mut()
+            .filter_map(|pages| {
+                let (_, envelope) = pages.data.front()?;
+                Some(((envelope.id.tx_seq, envelope.id.event_seq), pages))
+            })
+            .min_by_key(|(id, _)| *id)
+            .and_then(|(_, pages)| pages.data.pop_front());
+
+        Ok(next)
+    }
+}
+
+impl Pages {
+    /// Fetch the next page once the current one is consumed.
+    async fn fill(&mut self, rpc: &RpcPool) -> Result<()> {
+        while self.data.is_empty() && !self.done {
+            let (endpoint, page) = rpc
+                .query(|client| {
+                    let query = self.query.clone();
+                    let cursor = self.cursor.clone();
+                    async move {
+                        client
+                            .event_api()
+                            .get_events(query, cursor, Some(PAGE_SIZE), false)
+                            .await
+                    }
+                })
+                .await
+                .context("Failed to fetch historical events")?;
+
+            self.data
+                .extend(page.data.into_iter().map(|envelope| (endpoint, envelope)));
+            self.done = page.next_cursor.is_none();
+            self.cursor = page.next_cursor;
+        }
+
+        Ok(())
+    }
+}
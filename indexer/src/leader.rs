@@ -0,0 +1,64 @@
+use crate::config::HaConfig;
+use eyre::{Context, Result};
+use sqlx::{query, Connection, PgConnection};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Leadership among indexer replicas, held as a session-level Postgres advisory lock.
+///
+/// Postgres releases the lock as soon as the leader's connection is gone, so a standby
+/// polling for the lock takes over within one retry interval after the leader dies.
+pub struct Leadership {
+    connection: PgConnection,
+    heartbeat: Duration,
+}
+
+impl Leadership {
+    /// Block until this replica becomes the leader.
+    #[tracing::instrument(name = "Acquiring leadership", skip_all)]
+    pub async fn acquire(config: &HaConfig) -> Result<Self> {
+        let retry = Duration::from_secs(config.retry_interval_secs);
+        loop {
+            match try_acquire(config).await {
+                Ok(Some(connection)) => {
+                    info!("Became the leader");
+                    return Ok(Self {
+                        connection,
+                        heartbeat: Duration::from_secs(config.heartbeat_interval_secs),
+                    });
+                }
+                Ok(None) => info!("Another replica is the leader, standing by"),
+                Err(err) => warn!("Failed to acquire leadership. Error: {err:?}"),
+            }
+            tokio::time::sleep(retry).await;
+        }
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        self.heartbeat
+    }
+
+    /// Make sure the connection holding the lock is still alive.
+    pub async fn check(&mut self) -> Result<()> {
+        self.connection
+            .ping()
+            .await
+            .context("Lost the connection holding the leader lock")
+    }
+}
+
+async fn try_acquire(config: &HaConfig) -> Result<Option<PgConnection>> {
+    let mut connection = PgConnection::connect_with(&config.db.with_db())
+        .await
+        .context("Failed to connect to Postgres")?;
+    let locked = query!(
+        r#"SELECT pg_try_advisory_lock($1) as "locked!""#,
+        config.lock_id
+    )
+    .fetch_one(&mut connection)
+    .await
+    .context("Failed to try advisory lock")?
+    .locked;
+
+    Ok(locked.then_some(connection))
+}
@@ -1,45 +1,221 @@
-use cynic::MutationBuilder;
-use eyre::{ensure, Context};
+use cynic::{GraphQlResponse, MutationBuilder, QueryBuilder};
+use eyre::{ensure, eyre, Context};
 use reqwest::header;
 use serde::Serialize;
 use serde_json::Value;
-use tracing::info;
+use tracing::{info, warn};
 
 use graphql::insert_nft::{InsertNftMutation, InsertNftMutationArguments};
-use models::events::Event;
-use models::sui_sdk::error::SuiRpcResult;
+use models::events::{Diagnostic, Event, EventRegistry, ParseMode};
 use models::sui_sdk::rpc_types::SuiEventEnvelope;
-use models::{Item, Nft};
+use models::{Item, Network, Nft, TransactionRecord};
 
-use crate::config::Config;
+use crate::config::BackendConfig;
+use crate::dry_run::DryRun;
 use crate::graphql::add_item::{AddItemMutation, AddItemMutationArguments};
+use crate::graphql::delete_unknown_event::{
+    DeleteUnknownEventMutation, DeleteUnknownEventMutationArguments,
+};
+use crate::graphql::insert_transaction::{
+    InsertTransactionMutation, InsertTransactionMutationArguments,
+};
+use crate::graphql::insert_unknown_event::{
+    InsertUnknownEventMutation, InsertUnknownEventMutationArguments,
+};
+use crate::graphql::patch_nft::{PatchNftMutation, PatchNftMutationArguments};
 use crate::graphql::remove_item::{RemoveItemMutation, RemoveItemMutationArguments};
+use crate::graphql::unknown_events::{
+    UnknownEventKey, UnknownEventsQuery, UnknownEventsQueryArguments,
+};
+use crate::graphql::upsert_nft::{UpsertNftMutation, UpsertNftMutationArguments};
+use crate::rpc::VerifyError;
+use crate::scripting::{Operation, Scripts};
 
+mod admin;
+pub mod buffer;
+pub mod checkpoints;
 pub mod config;
+pub mod control;
+pub mod cursor;
+pub mod dry_run;
 mod graphql;
+pub mod history;
+pub mod leader;
+pub mod rpc;
+pub mod scripting;
+pub mod startup;
 pub mod telemetry;
 
-#[tracing::instrument(name = "Handling contract's event", err, skip_all)]
+#[tracing::instrument(
+    name = "Handling contract's event",
+    err,
+    skip_all,
+    fields(tx_digest = %sui_event.tx_digest)
+)]
+/// Returns diagnostics of the event's parsing, which are logged as well.
 pub async fn handle_contract_event(
-    contract_event: SuiRpcResult<SuiEventEnvelope>,
-    config: &Config,
-) -> eyre::Result<()> {
-    let sui_event = contract_event.context("Sui Rpc error")?;
+    sui_event: &SuiEventEnvelope,
+    backend: &BackendConfig,
+    network: Network,
+    scripts: &Scripts,
+    registry: &EventRegistry,
+    parse_mode: ParseMode,
+    dry_run: Option<&DryRun>,
+) -> eyre::Result<Vec<Diagnostic>> {
     info!("Getting new Sui's event");
-    let event = sui_event
-        .event
-        .try_into()
-        .context("Failed to convert `SuiEvent` into `Event`")?;
-    let query = build_query(event);
-    let resp = send_graphql_query(config, &query)
+    let queries = build_queries(sui_event, network, scripts, registry, parse_mode);
+    if let Ok((_, diagnostics)) = &queries {
+        for diagnostic in diagnostics {
+            warn!("Event was parsed with a diagnostic: {diagnostic}");
+        }
+    }
+    let (queries, diagnostics) = match (queries, dry_run) {
+        (Ok((queries, diagnostics)), Some(dry_run)) => {
+            dry_run.record_queries(sui_event, &queries, &diagnostics);
+            return Ok(diagnostics);
+        }
+        (Err(err), Some(dry_run)) => {
+            dry_run.record_error(sui_event, &err);
+            return Err(err);
+        }
+        (queries, None) => queries?,
+    };
+
+    for query in queries {
+        send_query(backend, &query).await?;
+    }
+
+    Ok(diagnostics)
+}
+
+/// Send an already parsed event of the envelope to the backend, bypassing Sui specific parsing.
+#[tracing::instrument(name = "Sending event", err, skip_all)]
+pub async fn send_event(
+    envelope: &SuiEventEnvelope,
+    event: Event,
+    backend: &BackendConfig,
+    network: Network,
+) -> eyre::Result<()> {
+    send_query(backend, &build_query(envelope, event, network)).await
+}
+
+/// Record a transaction which touched the contract's NFTs.
+#[tracing::instrument(name = "Sending transaction", err, skip_all, fields(digest = %transaction.digest))]
+pub async fn send_transaction(
+    transaction: TransactionRecord,
+    backend: &BackendConfig,
+    network: Network,
+) -> eyre::Result<()> {
+    let args = InsertTransactionMutationArguments {
+        transaction: transaction.into(),
+        network: Some(network.into()),
+    };
+    let query = serde_json::to_value(InsertTransactionMutation::build(args))?;
+    send_query(backend, &query).await
+}
+
+/// Page of unknown events stored by the backend, oldest first.
+pub(crate) async fn fetch_unknown_events(
+    backend: &BackendConfig,
+    network: Network,
+    limit: i64,
+    offset: i64,
+) -> eyre::Result<Vec<UnknownEventKey>> {
+    let args = UnknownEventsQueryArguments {
+        network: Some(network.into()),
+        limit,
+        offset,
+    };
+    let resp = send_graphql_query(backend, UnknownEventsQuery::build(args))
+        .await
+        .context("Failed to send request to GraphQL backend service")?
+        .error_for_status()
+        .context("Backend failed to handle the request")?;
+    let resp: GraphQlResponse<UnknownEventsQuery> = resp.json().await?;
+    if let Some(errors) = resp.errors.filter(|errors| !errors.is_empty()) {
+        return Err(eyre!("GraphQL response contains errors: {errors:?}"));
+    }
+
+    resp.data
+        .map(|data| data.unknown_events)
+        .ok_or_else(|| eyre!("GraphQL response has no data"))
+}
+
+pub(crate) async fn delete_unknown_event(
+    backend: &BackendConfig,
+    network: Network,
+    tx_digest: String,
+    event_seq: i64,
+) -> eyre::Result<()> {
+    let args = DeleteUnknownEventMutationArguments {
+        tx_digest,
+        event_seq,
+        network: Some(network.into()),
+    };
+    let query = serde_json::to_value(DeleteUnknownEventMutation::build(args))?;
+    send_query(backend, &query).await
+}
+
+async fn send_query(backend: &BackendConfig, query: &Value) -> eyre::Result<()> {
+    let resp = send_graphql_query(backend, query)
         .await
         .context("Failed to send request to GraphQL backend service")?;
-    handle_errors(resp).await?;
+    handle_errors(resp).await
+}
 
-    Ok(())
+fn build_queries(
+    sui_event: &SuiEventEnvelope,
+    network: Network,
+    scripts: &Scripts,
+    registry: &EventRegistry,
+    parse_mode: ParseMode,
+) -> eyre::Result<(Vec<Value>, Vec<Diagnostic>)> {
+    let ret = match scripts
+        .run(sui_event)
+        .context("Failed to run event script")?
+    {
+        Some(operations) => {
+            let queries = operations
+                .into_iter()
+                .map(|operation| build_operation_query(operation, network))
+                .collect();
+            (queries, Vec::new())
+        }
+        None => {
+            let parsed = registry
+                .parse(sui_event, parse_mode)
+                .context("Failed to convert `SuiEvent` into `Event`")?;
+            let query = build_query(sui_event, parsed.event, network);
+            (vec![query], parsed.diagnostics)
+        }
+    };
+
+    Ok(ret)
+}
+
+/// Whether the error means that the backend couldn't take the event right now, so it's
+/// worth retrying, as opposed to the event being rejected.
+pub fn is_backend_unavailable(err: &eyre::Report) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|err| !err.is_decode() && err.status().map_or(true, |status| status.is_server_error()))
+}
+
+/// Whether the error means that no endpoint could verify the event right now, so it's worth
+/// retrying, as opposed to the event not being confirmed.
+pub fn is_verifier_unavailable(err: &eyre::Report) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<VerifyError>(),
+            Some(VerifyError::Unavailable(_))
+        )
+    })
 }
 
 async fn handle_errors(resp: reqwest::Response) -> eyre::Result<()> {
+    let resp = resp
+        .error_for_status()
+        .context("Backend failed to handle the request")?;
     ensure!(
         resp.status().as_u16() == 200,
         "Response from backend contains error"
@@ -58,11 +234,11 @@ async fn handle_errors(resp: reqwest::Response) -> eyre::Result<()> {
 
 #[tracing::instrument(name = "Sending GraphQL query to backend server", skip_all)]
 async fn send_graphql_query(
-    config: &Config,
+    backend: &BackendConfig,
     query: impl Serialize,
 ) -> eyre::Result<reqwest::Response, reqwest::Error> {
     reqwest::Client::new()
-        .post(config.backend.graphql_url())
+        .post(backend.graphql_url())
         .header(header::CONTENT_TYPE, "application/json")
         .json(&query)
         .send()
@@ -70,42 +246,98 @@ async fn send_graphql_query(
 }
 
 #[tracing::instrument(name = "Building query for graphql", skip_all)]
-fn build_query(event: Event) -> Value {
+fn build_query(envelope: &SuiEventEnvelope, event: Event, network: Network) -> Value {
+    let network = Some(network.into());
     let ret = match event {
         Event::Nft(Nft {
             id,
+            network: _,
             r#type,
             owner,
+            ownership,
             url,
             traits,
             items,
             created_at,
             attached_to,
+            slot,
         }) => {
             let args = InsertNftMutationArguments {
                 id,
-                r#type,
+                r#type: r#type.into(),
                 owner,
+                ownership: ownership.map(Into::into),
                 url,
                 traits: traits.into_iter().map(Into::into).collect(),
                 items: items.into_iter().map(Into::into).collect(),
                 created_at,
                 attached_to,
+                slot: slot.map(Into::into),
+                network,
             };
             let query = InsertNftMutation::build(args);
             serde_json::to_value(query).unwrap()
         }
-        Event::ItemAdded(Item { lemon_id, item_id }) => {
-            let args = AddItemMutationArguments { lemon_id, item_id };
+        Event::ItemAdded(Item {
+            lemon_id,
+            item_id,
+            slot,
+        }) => {
+            let args = AddItemMutationArguments {
+                lemon_id,
+                item_id,
+                slot: slot.map(Into::into),
+                network,
+            };
             let query = AddItemMutation::build(args);
             serde_json::to_value(query).unwrap()
         }
-        Event::ItemRemoved(Item { lemon_id, item_id }) => {
-            let args = RemoveItemMutationArguments { lemon_id, item_id };
+        Event::ItemRemoved(Item {
+            lemon_id, item_id, ..
+        }) => {
+            let args = RemoveItemMutationArguments {
+                lemon_id,
+                item_id,
+                network,
+            };
             let query = RemoveItemMutation::build(args);
             serde_json::to_value(query).unwrap()
         }
+        Event::Unknown(event) => {
+            let args = InsertUnknownEventMutationArguments {
+                tx_digest: envelope.tx_digest.to_string(),
+                event_seq: envelope.id.event_seq,
+                event: event.into(),
+                network,
+            };
+            let query = InsertUnknownEventMutation::build(args);
+            serde_json::to_value(query).unwrap()
+        }
     };
 
     ret
 }
+
+#[tracing::instrument(name = "Building query for script's operation", skip_all)]
+fn build_operation_query(operation: Operation, network: Network) -> Value {
+    let network = Some(network.into());
+    match operation {
+        Operation::Upsert(nft) => {
+            let args = UpsertNftMutationArguments {
+                nft: nft.into(),
+                network,
+            };
+            let query = UpsertNftMutation::build(args);
+            serde_json::to_value(query).unwrap()
+        }
+        Operation::Patch { id, patch } => {
+            let args = PatchNftMutationArguments {
+                id,
+                patch: patch.into(),
+                network,
+            };
+            let query = PatchNftMutation::build(args);
+            serde_json::to_value(query).unwrap()
+        }
+    }
+}
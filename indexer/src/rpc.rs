@@ -0,0 +1,281 @@
+use crate::config::{SuiJsonRpcConfig, SuiRpcEndpointConfig};
+use eyre::{bail, ensure, eyre, Context, Result};
+use futures::Stream;
+use models::sui_sdk::error::SuiRpcResult;
+use models::sui_sdk::rpc_types::{SuiEventEnvelope, SuiEventFilter};
+use models::sui_sdk::{SuiClient, SuiClientBuilder};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+const MAX_SCORE: i64 = 100;
+const MIN_SCORE: i64 = -100;
+const SUCCESS_REWARD: i64 = 1;
+const FAILURE_PENALTY: i64 = 25;
+
+/// Set of Sui JSON-RPC endpoints ranked by health score.
+///
+/// Every successful call rewards an endpoint and every failure penalizes it, so requests and
+/// subscriptions always go to the healthiest endpoint first and fail over to the next one.
+pub struct RpcPool {
+    endpoints: Vec<Endpoint>,
+    http: reqwest::Client,
+}
+
+struct Endpoint {
+    config: SuiRpcEndpointConfig,
+    client: Mutex<Option<SuiClient>>,
+    /// Set on failure, the cached client is dropped and rebuilt by the next caller.
+    reconnect: AtomicBool,
+    score: AtomicI64,
+}
+
+impl RpcPool {
+    pub fn new(config: &SuiJsonRpcConfig) -> Result<Self> {
+        ensure!(
+            !config.endpoints.is_empty(),
+            "At least one Sui JSON-RPC endpoint must be configured"
+        );
+        ensure!(
+            !config.verify_events || config.endpoints.len() > 1,
+            "Events verification requires at least two Sui JSON-RPC endpoints"
+        );
+
+        let endpoints = config
+            .endpoints
+            .iter()
+            .cloned()
+            .map(|config| Endpoint {
+                config,
+                client: Mutex::new(None),
+                reconnect: AtomicBool::new(false),
+                score: AtomicI64::new(MAX_SCORE),
+            })
+            .collect();
+
+        Ok(Self {
+            endpoints,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    pub fn http_url(&self, endpoint: usize) -> &str {
+        &self.endpoints[endpoint].config.http_url
+    }
+
+    /// Endpoint indexes ordered from the healthiest to the least healthy one.
+    fn ranked(&self) -> Vec<usize> {
+        let mut ret: Vec<usize> = (0..self.endpoints.len()).collect();
+        ret.sort_by_key(|&idx| -self.endpoints[idx].score.load(Ordering::Relaxed));
+        ret
+    }
+
+    pub fn reward(&self, endpoint: usize) {
+        let score = &self.endpoints[endpoint].score;
+        let _ = score.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| {
+            Some((s + SUCCESS_REWARD).min(MAX_SCORE))
+        });
+    }
+
+    pub fn penalize(&self, endpoint: usize) {
+        let score = &self.endpoints[endpoint].score;
+        let _ = score.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |s| {
+            Some((s - FAILURE_PENALTY).max(MIN_SCORE))
+        });
+        // The client may be in use right now, so it's dropped by the next attempt which
+        // then reconnects from scratch.
+        self.endpoints[endpoint]
+            .reconnect
+            .store(true, Ordering::Relaxed);
+    }
+
+    async fn client(&self, endpoint: usize) -> Result<SuiClient> {
+        let Endpoint {
+            config,
+            client,
+            reconnect,
+            ..
+        } = &self.endpoints[endpoint];
+        let mut client = client.lock().await;
+        if reconnect.swap(false, Ordering::Relaxed) {
+            client.take();
+        }
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+
+        info!("Connecting to Sui JSON-RPC endpoint `{}`", config.http_url);
+        let built = SuiClientBuilder::default()
+            .ws_url(&config.ws_url)
+            .build(&config.http_url)
+            .await
+            .wrap_err_with(|| format!("Failed to build SuiClient for `{}`", config.http_url))?;
+        *client = Some(built.clone());
+
+        Ok(built)
+    }
+
+    /// Subscribe to events on the healthiest endpoint which accepts the subscription.
+    ///
+    /// Returns the index of the chosen endpoint alongside the stream, so the caller can
+    /// penalize it when the stream dies and verify events against another endpoint.
+    #[tracing::instrument(name = "Subscribing to Sui events", skip_all)]
+    pub async fn subscribe(
+        &self,
+        filter: SuiEventFilter,
+    ) -> Result<(usize, impl Stream<Item = SuiRpcResult<SuiEventEnvelope>>)> {
+        for endpoint in self.ranked() {
+            let subscription = match self.client(endpoint).await {
+                Ok(client) => client
+                    .event_api()
+                    .subscribe_event(filter.clone())
+                    .await
+                    .wrap_err("Failed to subscribe to events"),
+                Err(err) => Err(err),
+            };
+
+            match subscription {
+                Ok(stream) => {
+                    info!("Subscribed to events on `{}`", self.http_url(endpoint));
+                    return Ok((endpoint, stream));
+                }
+                Err(err) => {
+                    warn!("Endpoint `{}` failed: {err:?}", self.http_url(endpoint));
+                    self.penalize(endpoint);
+                }
+            }
+        }
+
+        bail!("All Sui JSON-RPC endpoints failed to subscribe to events")
+    }
+
+    /// Run a query against endpoints in order of their health until one of them succeeds.
+    ///
+    /// Returns the index of the endpoint which served the query alongside the result.
+    pub async fn query<F, Fut, T>(&self, f: F) -> Result<(usize, T)>
+    where
+        F: Fn(SuiClient) -> Fut,
+        Fut: Future<Output = SuiRpcResult<T>>,
+    {
+        self.query_excluding(None, f).await
+    }
+
+    async fn query_excluding<F, Fut, T>(&self, excluded: Option<usize>, f: F) -> Result<(usize, T)>
+    where
+        F: Fn(SuiClient) -> Fut,
+        Fut: Future<Output = SuiRpcResult<T>>,
+    {
+        for endpoint in self.ranked() {
+            if Some(endpoint) == excluded {
+                continue;
+            }
+
+            let ret = match self.client(endpoint).await {
+                Ok(client) => f(client).await.map_err(Into::into),
+                Err(err) => Err(err),
+            };
+
+            match ret {
+                Ok(ret) => {
+                    self.reward(endpoint);
+                    return Ok((endpoint, ret));
+                }
+                Err(err) => {
+                    warn!("Endpoint `{}` failed: {err:?}", self.http_url(endpoint));
+                    self.penalize(endpoint);
+                }
+            }
+        }
+
+        bail!("All Sui JSON-RPC endpoints failed to serve the query")
+    }
+
+    /// Call a JSON-RPC method which `SuiClient` doesn't expose, failing over between
+    /// endpoints the same way as [`RpcPool::query`].
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<(usize, T)> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        for endpoint in self.ranked() {
+            match self.call_endpoint(endpoint, &request).await {
+                Ok(ret) => {
+                    self.reward(endpoint);
+                    return Ok((endpoint, ret));
+                }
+                Err(err) => {
+                    warn!("Endpoint `{}` failed: {err:?}", self.http_url(endpoint));
+                    self.penalize(endpoint);
+                }
+            }
+        }
+
+        bail!("All Sui JSON-RPC endpoints failed to serve `{method}`")
+    }
+
+    async fn call_endpoint<T: DeserializeOwned>(
+        &self,
+        endpoint: usize,
+        request: &Value,
+    ) -> Result<T> {
+        let mut resp: Value = self
+            .http
+            .post(self.http_url(endpoint))
+            .json(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = resp.get("error") {
+            bail!("JSON-RPC error: {error}");
+        }
+        serde_json::from_value(resp["result"].take()).wrap_err("Unexpected JSON-RPC result")
+    }
+
+    /// Confirm the event against an endpoint other than the one it was received from,
+    /// by fetching its transaction and looking for the very same event in the effects.
+    #[tracing::instrument(name = "Verifying Sui event", skip_all, fields(tx_digest = %envelope.tx_digest))]
+    pub async fn verify_event(
+        &self,
+        source: usize,
+        envelope: &SuiEventEnvelope,
+    ) -> Result<(), VerifyError> {
+        let digest = envelope.tx_digest;
+        let (verifier, tx) = self
+            .query_excluding(Some(source), |client| async move {
+                client.read_api().get_transaction(digest).await
+            })
+            .await
+            .map_err(|err| VerifyError::Unavailable(err.into()))?;
+
+        if !tx.effects.events.contains(&envelope.event) {
+            self.penalize(source);
+            return Err(VerifyError::NotConfirmed {
+                source_url: self.http_url(source).to_string(),
+                verifier_url: self.http_url(verifier).to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Why an event couldn't be verified.
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError {
+    /// No other endpoint served the event's transaction, so the event is neither confirmed
+    /// nor denied and may be verified later.
+    #[error("No other endpoint could fetch the event's transaction")]
+    Unavailable(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("Event from `{source_url}` wasn't confirmed by `{verifier_url}`")]
+    NotConfirmed {
+        source_url: String,
+        verifier_url: String,
+    },
+}
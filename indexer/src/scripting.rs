@@ -0,0 +1,153 @@
+use crate::config::ScriptConfig;
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Context, Result};
+use models::events::event_time;
+use models::move_type::StructTag;
+use models::sui_sdk::rpc_types::{SuiEvent, SuiEventEnvelope, SuiMoveStruct};
+use models::{Address, Nft, NftKind, NftPatch, ObjectId, Trait};
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Operation emitted by a script, applied by the backend as a generic mutation.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Upsert(Nft),
+    Patch { id: ObjectId, patch: NftPatch },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum ScriptOperation {
+    Upsert(NftUpsert),
+    Patch {
+        id: ObjectId,
+        #[serde(flatten)]
+        patch: NftPatch,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct NftUpsert {
+    id: ObjectId,
+    r#type: NftKind,
+    /// Defaults to the sender of the event.
+    owner: Option<Address>,
+    url: String,
+    #[serde(default)]
+    traits: Vec<Trait>,
+}
+
+impl ScriptOperation {
+    fn into_operation(self, sender: &Address, created_at: DateTime<Utc>) -> Operation {
+        match self {
+            ScriptOperation::Upsert(upsert) => Operation::Upsert(Nft {
+                id: upsert.id,
+                network: None,
+                r#type: upsert.r#type,
+                owner: upsert.owner.unwrap_or_else(|| sender.clone()),
+                ownership: None,
+                url: upsert.url,
+                traits: upsert.traits,
+                items: Vec::new(),
+                created_at,
+                attached_to: None,
+                slot: None,
+            }),
+            ScriptOperation::Patch { id, patch } => Operation::Patch { id, patch },
+        }
+    }
+}
+
+/// User-provided Rhai scripts keyed by fully qualified Move event type, parsed so that the
+/// spellings of the same address, e.g. with or without leading zeros, find the same script.
+///
+/// A script gets the `event` map in its scope, with `type`, `sender`, `tx_digest`,
+/// `timestamp` and the event's `fields`, and evaluates to one operation map or an array
+/// of them, e.g. `#{ op: "patch", id: event.fields.lemon_id, owner: event.sender }`.
+pub struct Scripts {
+    engine: Engine,
+    scripts: HashMap<StructTag, AST>,
+}
+
+impl Scripts {
+    pub fn load(configs: &[ScriptConfig], max_operations: u64) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(max_operations);
+
+        let mut scripts = HashMap::new();
+        for ScriptConfig { event_type, path } in configs {
+            let event_type: StructTag = event_type
+                .parse()
+                .with_context(|| format!("Invalid event type of script `{}`", path.display()))?;
+            let ast = engine
+                .compile_file(path.clone())
+                .map_err(|e| eyre!("Failed to compile script `{}`: {e}", path.display()))?;
+            scripts.insert(event_type, ast);
+        }
+
+        Ok(Self { engine, scripts })
+    }
+
+    /// Run the script registered for the event's type.
+    ///
+    /// Returns `None` if there is no such script, so the event goes to the built-in parsers.
+    #[tracing::instrument(name = "Running event script", skip_all)]
+    pub fn run(&self, envelope: &SuiEventEnvelope) -> Result<Option<Vec<Operation>>> {
+        let SuiEvent::MoveEvent {
+            sender,
+            type_,
+            fields,
+            ..
+        } = &envelope.event
+        else {
+            return Ok(None);
+        };
+        let Some(ast) = type_
+            .parse::<StructTag>()
+            .ok()
+            .and_then(|event_type| self.scripts.get(&event_type))
+        else {
+            return Ok(None);
+        };
+
+        let fields = match fields {
+            Some(SuiMoveStruct::WithFields(fields)) => serde_json::to_value(fields)?,
+            Some(SuiMoveStruct::WithTypes { fields, .. }) => serde_json::to_value(fields)?,
+            _ => json!({}),
+        };
+        let sender = Address::from(*sender);
+        let event = json!({
+            "type": type_,
+            "sender": sender,
+            "tx_digest": envelope.tx_digest.to_string(),
+            "timestamp": envelope.timestamp,
+            "fields": fields,
+        });
+
+        let mut scope = Scope::new();
+        let event = rhai::serde::to_dynamic(event).map_err(|e| eyre!("{e}"))?;
+        scope.push_constant("event", event);
+        let ret: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, ast)
+            .map_err(|e| eyre!("Script for `{type_}` failed: {e}"))?;
+
+        let ret = if ret.is_array() {
+            ret
+        } else {
+            Dynamic::from_array(vec![ret])
+        };
+        let operations: Vec<ScriptOperation> = rhai::serde::from_dynamic(&ret)
+            .map_err(|e| eyre!("{e}"))
+            .with_context(|| format!("Script for `{type_}` returned invalid operations"))?;
+
+        Ok(Some(
+            operations
+                .into_iter()
+                .map(|operation| operation.into_operation(&sender, event_time(envelope)))
+                .collect(),
+        ))
+    }
+}
@@ -0,0 +1,809 @@
+use crate::admin::{setup_admin_server, AdminServer};
+use crate::buffer::{Delivery, EventBuffer};
+use crate::checkpoints::{fetch_checkpoint, latest_checkpoint};
+use crate::config::{Config, IngestionConfig};
+use crate::control::{Command, Control, RewindTarget};
+use crate::cursor::{is_after, CheckpointFile, CursorStore};
+use crate::dry_run::DryRun;
+use crate::history::{contract_queries, History};
+use crate::leader::Leadership;
+use crate::rpc::RpcPool;
+use crate::scripting::Scripts;
+use crate::{
+    delete_unknown_event, fetch_unknown_events, handle_contract_event, is_backend_unavailable,
+    is_verifier_unavailable, send_transaction,
+};
+use chrono::Utc;
+use eyre::{bail, ensure, eyre, Context, Result};
+use futures::StreamExt;
+use models::events::EventRegistry;
+use models::sui_sdk::rpc_types::{EventID, EventQuery, SuiEvent, SuiEventEnvelope, SuiEventFilter};
+use models::sui_sdk::types::base_types::{ObjectID, TransactionDigest};
+use models::TransactionRecord;
+use sqlx::postgres::PgPoolOptions;
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+const UNKNOWN_EVENTS_PAGE_SIZE: i64 = 100;
+/// Checkpoints indexed in one go, so admin commands aren't starved while catching up.
+const CHECKPOINTS_PER_POLL: usize = 10;
+
+/// Received events waiting for the pipeline. A busy pipeline, e.g. while rewinding, holds
+/// the subscription back instead of queueing events without bound, the ones the node drops
+/// meanwhile are backfilled after resubscribing.
+const RECEIVED_EVENTS_CAPACITY: usize = 1024;
+
+type EventsSender = mpsc::Sender<(usize, SuiEventEnvelope)>;
+
+pub struct Indexer {
+    pipeline: Pipeline,
+    commands: mpsc::Receiver<Command>,
+    admin_port: Option<u16>,
+    admin_server: Option<AdminServer>,
+}
+
+impl Indexer {
+    #[tracing::instrument(name = "Building indexer", skip_all)]
+    pub async fn build(config: Config) -> Result<Self> {
+        info!("Setup Sui JSON-RPC endpoints");
+        let rpc = RpcPool::new(&config.sui_json_rpc).context("Failed to setup RPC endpoints")?;
+        let package = ObjectID::from_hex_literal(&config.sui_contract.address)
+            .context("Failed to parse contract address")?;
+        let registry = EventRegistry::contract(
+            std::iter::once(package.into())
+                .chain(config.sui_contract.additional_packages.iter().cloned()),
+        )
+        .with_decoder(config.event_decoder);
+        info!("Loading event scripts");
+        let scripts = Scripts::load(&config.scripting.scripts, config.scripting.max_operations)
+            .context("Failed to load event scripts")?;
+        let (control, commands) = Control::new();
+        let control = Arc::new(control);
+        let dry_run = config.dry_run.then(DryRun::new);
+        // A dry run must not move the cursor shared with the production replicas.
+        let cursor = match (&config.ha, &dry_run) {
+            (Some(ha), None) => {
+                let pool = PgPoolOptions::new()
+                    .acquire_timeout(Duration::from_secs(2))
+                    .connect_lazy_with(ha.db.with_db());
+                Some(CursorStore::new(pool, ha.cursor_name()))
+            }
+            _ => None,
+        };
+        // Without `ha` the checkpoint is kept locally, a dry run doesn't keep it at all.
+        let checkpoint_file = match &config.ingestion {
+            IngestionConfig::Checkpoints {
+                checkpoint_file, ..
+            } if cursor.is_none() && dry_run.is_none() => {
+                Some(CheckpointFile::new(checkpoint_file.clone()))
+            }
+            _ => None,
+        };
+        let buffer = match (&config.buffer, &dry_run) {
+            (Some(buffer), None) => {
+                Some(EventBuffer::open(buffer).context("Failed to open events buffer")?)
+            }
+            _ => None,
+        };
+
+        let (admin_port, admin_server) = match &config.admin {
+            Some(admin) => {
+                let admin_addr = format!("{}:{}", admin.host, admin.port);
+                info!("Binding address - {admin_addr} for admin API");
+                let listener = TcpListener::bind(&admin_addr)
+                    .context("Failed to bind address for admin API")?;
+                let port = listener.local_addr()?.port();
+                let server = setup_admin_server(listener, control.clone())
+                    .context("Failed to setup admin server")?;
+                (Some(port), Some(server))
+            }
+            None => (None, None),
+        };
+
+        let pipeline = Pipeline {
+            config,
+            package,
+            rpc: Arc::new(rpc),
+            control,
+            scripts,
+            registry,
+            dry_run,
+            cursor,
+            checkpoint_file,
+            buffer,
+            checkpoint: Mutex::new(CheckpointProgress::default()),
+            last_transaction: Mutex::new(None),
+        };
+        pipeline.report_queue();
+
+        Ok(Self {
+            pipeline,
+            commands,
+            admin_port,
+            admin_server,
+        })
+    }
+
+    pub fn admin_port(&self) -> Option<u16> {
+        self.admin_port
+    }
+
+    #[tracing::instrument(name = "Starting indexer", skip_all)]
+    pub async fn run_until_stopped(self) -> Result<()> {
+        let Self {
+            pipeline,
+            mut commands,
+            admin_server,
+            ..
+        } = self;
+
+        if let Some(admin_server) = admin_server {
+            tokio::spawn(async move {
+                if let Err(err) = admin_server.await {
+                    error!("Admin server failed. Error: {err:?}");
+                }
+            });
+        }
+
+        let mut leadership = match &pipeline.config.ha {
+            Some(ha) if pipeline.dry_run.is_none() => Some(Leadership::acquire(ha).await?),
+            _ => None,
+        };
+
+        let (events_tx, mut events_rx) = mpsc::channel(RECEIVED_EVENTS_CAPACITY);
+        let poll_interval = match pipeline.config.ingestion {
+            IngestionConfig::Subscription => {
+                // Subscribe before catching up, so events emitted meanwhile are buffered, not lost.
+                tokio::spawn(subscribe_events(
+                    pipeline.rpc.clone(),
+                    pipeline.control.clone(),
+                    pipeline.package,
+                    events_tx,
+                    Duration::from_secs(pipeline.config.sui_json_rpc.failover_delay_secs),
+                ));
+                pipeline.catch_up().await.context("Failed to catch up")?;
+                None
+            }
+            IngestionConfig::Checkpoints {
+                start_checkpoint,
+                poll_interval_ms,
+                ..
+            } => {
+                pipeline
+                    .restore_checkpoint(start_checkpoint)
+                    .await
+                    .context("Failed to load checkpoint")?;
+                Some(Duration::from_millis(poll_interval_ms))
+            }
+        };
+        let mut next_poll = Instant::now();
+
+        let heartbeat_interval = leadership
+            .as_ref()
+            .map_or(Duration::from_secs(60), Leadership::heartbeat_interval);
+        let mut heartbeat = tokio::time::interval(heartbeat_interval);
+        let mut backoff = pipeline.config.buffer.as_ref().map_or_else(
+            || Backoff::new(Duration::from_secs(1), Duration::from_secs(1)),
+            |buffer| {
+                Backoff::new(
+                    Duration::from_secs(buffer.retry_interval_secs),
+                    Duration::from_secs(buffer.max_retry_interval_secs),
+                )
+            },
+        );
+        let mut retry_at = None;
+        let mut paused = pipeline.control.subscribe_paused();
+        let ctrl_c = tokio::signal::ctrl_c();
+        futures::pin_mut!(ctrl_c);
+        loop {
+            let is_paused = *paused.borrow();
+            tokio::select! {
+                Some(command) = commands.recv() => pipeline.handle_command(command).await,
+                // With the on-disk buffer events keep being received while paused.
+                Some((endpoint, envelope)) = events_rx.recv(), if !is_paused || pipeline.buffer.is_some() => {
+                    pipeline.control.event_unbuffered();
+                    pipeline.receive(envelope, endpoint).await?;
+                }
+                delivery = next_delivery(pipeline.buffer.as_ref(), retry_at), if !is_paused => {
+                    retry_at = if pipeline.deliver(delivery?).await? {
+                        backoff.reset();
+                        None
+                    } else {
+                        Some(Instant::now() + backoff.next_delay())
+                    };
+                }
+                _ = tokio::time::sleep_until(next_poll), if poll_interval.is_some() && !is_paused => {
+                    next_poll = match pipeline.index_checkpoints().await {
+                        Ok(caught_up) => {
+                            backoff.reset();
+                            match (caught_up, poll_interval) {
+                                (true, Some(poll_interval)) => Instant::now() + poll_interval,
+                                _ => Instant::now(),
+                            }
+                        }
+                        Err(err) => {
+                            warn!("Failed to index checkpoints. Error: {err:?}");
+                            Instant::now() + backoff.next_delay()
+                        }
+                    };
+                }
+                Ok(()) = paused.changed() => {}
+                _ = heartbeat.tick(), if leadership.is_some() => {
+                    if let Some(leadership) = leadership.as_mut() {
+                        leadership.check().await?;
+                    }
+                }
+                _ = &mut ctrl_c, if pipeline.dry_run.is_some() => {
+                    pipeline.print_dry_run_summary();
+                    return Ok(());
+                }
+                else => {
+                    pipeline.print_dry_run_summary();
+                    bail!("Failed to get new events from Sui Node")
+                }
+            }
+        }
+    }
+}
+
+/// Keep the events subscription alive, failing over between RPC endpoints, and forward
+/// received events to the pipeline.
+///
+/// Events emitted while resubscribing are fetched after the last received one and forwarded
+/// before the new subscription's events, the pipeline skips the ones it gets twice.
+async fn subscribe_events(
+    rpc: Arc<RpcPool>,
+    control: Arc<Control>,
+    package: ObjectID,
+    events: EventsSender,
+    failover_delay: Duration,
+) {
+    let filter = SuiEventFilter::Package(package);
+    let mut last_seen: Option<EventID> = None;
+    loop {
+        let (endpoint, contract_events) = match rpc.subscribe(filter.clone()).await {
+            Ok(subscription) => subscription,
+            Err(err) => {
+                error!("Failed to subscribe to events. Error: {err:?}");
+                tokio::time::sleep(failover_delay).await;
+                continue;
+            }
+        };
+        futures::pin_mut!(contract_events);
+
+        if let Some(cursor) = last_seen.clone() {
+            match missed_events(&rpc, package, cursor).await {
+                Ok(missed) => {
+                    info!(
+                        "Backfilling {} events missed while failing over",
+                        missed.len()
+                    );
+                    for (endpoint, envelope) in missed {
+                        if !forward(&control, &events, &mut last_seen, endpoint, envelope).await {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => error!("Failed to backfill events after failover. Error: {err:?}"),
+            }
+        }
+
+        info!("Start to poll Sui Node `{}`", rpc.http_url(endpoint));
+        while let Some(contract_event) = contract_events.next().await {
+            match contract_event {
+                Ok(envelope) => {
+                    if !forward(&control, &events, &mut last_seen, endpoint, envelope).await {
+                        return;
+                    }
+                }
+                Err(err) => error!("Sui Rpc error. Error: {err:?}"),
+            }
+        }
+
+        warn!(
+            "Events stream from `{}` is closed, failing over",
+            rpc.http_url(endpoint)
+        );
+        rpc.penalize(endpoint);
+    }
+}
+
+/// Returns `false` once the pipeline is gone.
+async fn forward(
+    control: &Control,
+    events: &EventsSender,
+    last_seen: &mut Option<EventID>,
+    endpoint: usize,
+    envelope: SuiEventEnvelope,
+) -> bool {
+    if last_seen
+        .as_ref()
+        .map_or(true, |seen| is_after(&envelope.id, seen))
+    {
+        *last_seen = Some(envelope.id.clone());
+    }
+    control.event_buffered();
+    events.send((endpoint, envelope)).await.is_ok()
+}
+
+/// Contract events emitted after the cursor, in order.
+async fn missed_events(
+    rpc: &RpcPool,
+    package: ObjectID,
+    cursor: EventID,
+) -> Result<Vec<(usize, SuiEventEnvelope)>> {
+    let mut history = History::new(rpc, contract_queries(package), Some(cursor));
+    let mut ret = Vec::new();
+    while let Some(event) = history.next().await? {
+        ret.push(event);
+    }
+
+    Ok(ret)
+}
+
+/// Wait for the retry deadline, if any, and then for the next buffered delivery.
+async fn next_delivery(
+    buffer: Option<&EventBuffer>,
+    retry_at: Option<Instant>,
+) -> Result<Delivery> {
+    let Some(buffer) = buffer else {
+        return futures::future::pending().await;
+    };
+    if let Some(retry_at) = retry_at {
+        tokio::time::sleep_until(retry_at).await;
+    }
+
+    buffer.next().await
+}
+
+/// Exponentially growing delay between delivery attempts while the backend is unavailable.
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = self.initial;
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+}
+
+/// Next checkpoint to index and how many of its contract events are already applied,
+/// so a checkpoint interrupted by a backend outage is resumed rather than re-applied.
+#[derive(Clone, Copy, Debug, Default)]
+struct CheckpointProgress {
+    next: u64,
+    applied: usize,
+}
+
+struct Pipeline {
+    config: Config,
+    package: ObjectID,
+    rpc: Arc<RpcPool>,
+    control: Arc<Control>,
+    scripts: Scripts,
+    registry: EventRegistry,
+    dry_run: Option<DryRun>,
+    cursor: Option<CursorStore>,
+    /// Keeps the last indexed checkpoint in the checkpoint ingestion mode without `ha`.
+    checkpoint_file: Option<CheckpointFile>,
+    buffer: Option<EventBuffer>,
+    checkpoint: Mutex<CheckpointProgress>,
+    last_transaction: Mutex<Option<TransactionDigest>>,
+}
+
+impl Pipeline {
+    /// Returns `false` if the backend or the endpoints verifying the event are unavailable,
+    /// then the event isn't marked as processed and may be retried.
+    async fn apply(&self, envelope: &SuiEventEnvelope, endpoint: usize) -> bool {
+        let ret = self.verify_and_handle(envelope, endpoint).await;
+        if let Err(err) = &ret {
+            if is_backend_unavailable(err) {
+                warn!("Backend is unavailable. Error: {err:?}");
+                return false;
+            }
+            if is_verifier_unavailable(err) {
+                warn!("No endpoint can verify the event. Error: {err:?}");
+                return false;
+            }
+            error!("An error is occurring while I handle contract events. Error: {err:?}");
+        }
+        self.control.event_processed(envelope, ret.is_ok());
+
+        // Checkpoints are the cursor in the checkpoint ingestion mode. A failed event keeps
+        // the cursor behind it, so a new leader retries it.
+        if let (Some(cursor), false, true) = (&self.cursor, self.walks_checkpoints(), ret.is_ok()) {
+            if let Err(err) = cursor.save(&envelope.id).await {
+                error!("Failed to save cursor. Error: {err:?}");
+            }
+        }
+
+        true
+    }
+
+    /// Apply the event unless it's already behind the cursor.
+    async fn apply_new(&self, envelope: &SuiEventEnvelope, endpoint: usize) -> bool {
+        match self.control.status().cursor {
+            Some(cursor) if !is_after(&envelope.id, &cursor) => true,
+            _ => self.apply(envelope, endpoint).await,
+        }
+    }
+
+    /// Put a received event into the on-disk buffer, or apply it right away without one.
+    async fn receive(&self, envelope: SuiEventEnvelope, endpoint: usize) -> Result<()> {
+        let Some(buffer) = &self.buffer else {
+            if !self.apply_new(&envelope, endpoint).await {
+                error!(
+                    "Event of tx `{}` is lost, configure `buffer` to retry such events",
+                    envelope.tx_digest
+                );
+                self.control.event_processed(&envelope, false);
+            }
+            return Ok(());
+        };
+
+        buffer
+            .push(endpoint, envelope)
+            .await
+            .context("Failed to buffer event")?;
+        self.report_queue();
+
+        Ok(())
+    }
+
+    /// Returns `false` if the delivery failed and must be retried later.
+    async fn deliver(&self, delivery: Delivery) -> Result<bool> {
+        let Some(buffer) = &self.buffer else {
+            return Ok(true);
+        };
+
+        match delivery {
+            Delivery::Event { seq, event } => {
+                if !self.apply_new(&event.envelope, event.endpoint).await {
+                    return Ok(false);
+                }
+                buffer.remove(seq).await?;
+            }
+            Delivery::Backfill { since_ms } => {
+                // Events are queued again before the end of the range is taken, so none
+                // falls in between.
+                buffer.resume_after_gap().await?;
+                let query = EventQuery::TimeRange {
+                    start_time: since_ms,
+                    end_time: Utc::now().timestamp_millis() as u64,
+                };
+                match self.replay(vec![query], None, true).await {
+                    Ok(count) => info!("Backfilled {count} events dropped by the full buffer"),
+                    Err(err) => {
+                        warn!("Failed to backfill events. Error: {err:?}");
+                        return Ok(false);
+                    }
+                }
+                buffer.close_gap().await?;
+            }
+        }
+        self.report_queue();
+
+        Ok(true)
+    }
+
+    fn report_queue(&self) {
+        if let Some(buffer) = &self.buffer {
+            self.control
+                .queue_changed(buffer.len(), buffer.bytes(), buffer.has_gap());
+        }
+    }
+
+    /// Apply events missed since the shared cursor, e.g. while there was no leader.
+    #[tracing::instrument(name = "Catching up from cursor", skip_all)]
+    async fn catch_up(&self) -> Result<()> {
+        let Some(store) = &self.cursor else {
+            return Ok(());
+        };
+        let Some(cursor) = store.load().await.context("Failed to load cursor")? else {
+            return Ok(());
+        };
+
+        self.control.restore_cursor(cursor.clone());
+        let count = self
+            .replay(contract_queries(self.package), Some(cursor), true)
+            .await?;
+        info!("Caught up, {count} events were applied");
+
+        Ok(())
+    }
+
+    fn walks_checkpoints(&self) -> bool {
+        matches!(self.config.ingestion, IngestionConfig::Checkpoints { .. })
+    }
+
+    async fn restore_checkpoint(&self, start_checkpoint: u64) -> Result<()> {
+        let indexed = match (&self.cursor, &self.checkpoint_file) {
+            (Some(store), _) => store.load_checkpoint().await?,
+            (None, Some(file)) => file.load().await?,
+            (None, None) => None,
+        };
+        let next = indexed.map_or(start_checkpoint, |indexed| indexed + 1);
+        info!("Indexing checkpoints from {next}");
+
+        *self.checkpoint.lock().unwrap() = CheckpointProgress { next, applied: 0 };
+        self.control.checkpoint_indexed(indexed);
+
+        Ok(())
+    }
+
+    /// Index checkpoints up to the latest one and return whether it's reached.
+    #[tracing::instrument(name = "Indexing checkpoints", skip_all)]
+    async fn index_checkpoints(&self) -> Result<bool> {
+        let latest = latest_checkpoint(&self.rpc).await?;
+        for _ in 0..CHECKPOINTS_PER_POLL {
+            let CheckpointProgress { next, applied } = *self.checkpoint.lock().unwrap();
+            if next > latest {
+                return Ok(true);
+            }
+
+            let checkpoint = fetch_checkpoint(&self.rpc, next).await?;
+            let events = checkpoint
+                .events
+                .into_iter()
+                .filter(|(_, envelope)| self.is_contract_event(&envelope.event))
+                .skip(applied);
+            for (endpoint, envelope) in events {
+                if !self.apply(&envelope, endpoint).await {
+                    bail!("Backend or verifying endpoints are unavailable");
+                }
+                self.checkpoint.lock().unwrap().applied += 1;
+            }
+
+            if let Some(cursor) = &self.cursor {
+                cursor
+                    .save_checkpoint(next)
+                    .await
+                    .context("Failed to save checkpoint")?;
+            } else if let Some(file) = &self.checkpoint_file {
+                file.save(next).await.context("Failed to save checkpoint")?;
+            }
+            *self.checkpoint.lock().unwrap() = CheckpointProgress {
+                next: next + 1,
+                applied: 0,
+            };
+            self.control.checkpoint_indexed(Some(next));
+        }
+
+        Ok(false)
+    }
+
+    fn rewind_to_checkpoint(&self, checkpoint: u64) -> Result<()> {
+        ensure!(
+            self.walks_checkpoints(),
+            "Rewinding to a checkpoint requires the checkpoint ingestion mode"
+        );
+        *self.checkpoint.lock().unwrap() = CheckpointProgress {
+            next: checkpoint,
+            applied: 0,
+        };
+        self.control.checkpoint_indexed(checkpoint.checked_sub(1));
+
+        Ok(())
+    }
+
+    async fn verify_and_handle(&self, envelope: &SuiEventEnvelope, endpoint: usize) -> Result<()> {
+        if self.config.sui_json_rpc.verify_events {
+            self.rpc
+                .verify_event(endpoint, envelope)
+                .await
+                .context("Failed to verify event")?;
+        }
+
+        let diagnostics = handle_contract_event(
+            envelope,
+            &self.config.backend,
+            self.config.network,
+            &self.scripts,
+            &self.registry,
+            self.config.parse_mode,
+            self.dry_run.as_ref(),
+        )
+        .await?;
+        self.control.diagnostics_reported(diagnostics.len());
+
+        // The event is applied by now, failing it would get it retried and applied twice.
+        if self.dry_run.is_none() {
+            if let Err(err) = self.record_transaction(envelope).await {
+                warn!("Failed to record transaction. Error: {err:?}");
+                self.control.transaction_unrecorded();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send the event's transaction to the backend, once per transaction.
+    #[tracing::instrument(name = "Recording transaction", skip_all, fields(tx_digest = %envelope.tx_digest))]
+    async fn record_transaction(&self, envelope: &SuiEventEnvelope) -> Result<()> {
+        let digest = envelope.tx_digest;
+        if *self.last_transaction.lock().unwrap() == Some(digest) {
+            return Ok(());
+        }
+
+        let (_, tx) = self
+            .rpc
+            .query(|client| async move { client.read_api().get_transaction(digest).await })
+            .await
+            .context("Failed to fetch transaction")?;
+        // Events are applied while their checkpoint is indexed, so it's the transaction's.
+        let checkpoint = self
+            .walks_checkpoints()
+            .then(|| self.checkpoint.lock().unwrap().next);
+        let transaction = TransactionRecord::from_response(&tx, envelope.timestamp, checkpoint);
+        send_transaction(transaction, &self.config.backend, self.config.network).await?;
+        *self.last_transaction.lock().unwrap() = Some(digest);
+
+        Ok(())
+    }
+
+    fn print_dry_run_summary(&self) {
+        if let Some(dry_run) = &self.dry_run {
+            dry_run.print_summary();
+        }
+    }
+
+    async fn handle_command(&self, command: Command) {
+        match command {
+            Command::Rewind(RewindTarget::Checkpoint(checkpoint)) => {
+                match self.rewind_to_checkpoint(checkpoint) {
+                    Ok(()) => info!("Rewound to checkpoint {checkpoint}"),
+                    Err(err) => error!("Failed to rewind. Error: {err:?}"),
+                }
+            }
+            Command::Rewind(target) => match self.rewind(target).await {
+                Ok(count) => info!("Rewind is finished, {count} events were re-applied"),
+                Err(err) => error!("Failed to rewind. Error: {err:?}"),
+            },
+            Command::Reprocess { digest, reply } => {
+                let ret = self
+                    .replay(vec![EventQuery::Transaction(digest)], None, false)
+                    .await;
+                let _ = reply.send(ret);
+            }
+            Command::ReprocessUnknown { reply } => {
+                let _ = reply.send(self.reprocess_unknown().await);
+            }
+        }
+    }
+
+    /// Replay stored unknown events whose types got a handler since, then drop them from the
+    /// backend.
+    #[tracing::instrument(name = "Re-processing unknown events", skip(self))]
+    async fn reprocess_unknown(&self) -> Result<usize> {
+        let (backend, network) = (&self.config.backend, self.config.network);
+        let (mut count, mut offset) = (0, 0);
+        loop {
+            let events = fetch_unknown_events(backend, network, UNKNOWN_EVENTS_PAGE_SIZE, offset)
+                .await
+                .context("Failed to fetch unknown events")?;
+            if events.is_empty() {
+                break;
+            }
+
+            for event in events {
+                // Events which are still unknown stay where they are, so the next page
+                // starts after them.
+                if !self.registry.handles(&event.r#type) {
+                    offset += 1;
+                    continue;
+                }
+                let digest = TransactionDigest::from_str(&event.tx_digest)
+                    .map_err(|e| eyre!("Invalid digest `{}`: {e}", event.tx_digest))?;
+                if !self.replay_event(digest, event.event_seq).await? {
+                    warn!(
+                        "Unknown event {}:{} isn't found on the node, it's kept",
+                        event.tx_digest, event.event_seq
+                    );
+                    offset += 1;
+                    continue;
+                }
+                delete_unknown_event(backend, network, event.tx_digest, event.event_seq)
+                    .await
+                    .context("Failed to delete re-processed unknown event")?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Apply the transaction's event at `event_seq` alone, the transaction's other events
+    /// were applied already. Returns whether the event was found.
+    async fn replay_event(&self, digest: TransactionDigest, event_seq: i64) -> Result<bool> {
+        let mut history = History::new(&self.rpc, vec![EventQuery::Transaction(digest)], None);
+        while let Some((endpoint, envelope)) = history.next().await? {
+            if envelope.id.event_seq != event_seq {
+                continue;
+            }
+            if !self.apply(&envelope, endpoint).await {
+                bail!("Backend or verifying endpoints are unavailable");
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    #[tracing::instrument(name = "Rewinding events", skip(self))]
+    async fn rewind(&self, target: RewindTarget) -> Result<usize> {
+        ensure!(
+            !self.walks_checkpoints(),
+            "Only rewinding to a checkpoint is supported in the checkpoint ingestion mode"
+        );
+        let (queries, cursor) = match target {
+            RewindTarget::Cursor(cursor) => (contract_queries(self.package), Some(cursor)),
+            RewindTarget::Timestamp(timestamp) => {
+                let query = EventQuery::TimeRange {
+                    start_time: timestamp.timestamp_millis() as u64,
+                    end_time: Utc::now().timestamp_millis() as u64,
+                };
+                (vec![query], None)
+            }
+            RewindTarget::Checkpoint(_) => {
+                bail!("Rewinding to a checkpoint requires the checkpoint ingestion mode")
+            }
+        };
+
+        self.replay(queries, cursor, false).await
+    }
+
+    /// Fetch historical events matching any of the queries and apply the contract's ones
+    /// in order.
+    ///
+    /// With `only_new` events which are already behind the cursor are skipped.
+    async fn replay(
+        &self,
+        queries: Vec<EventQuery>,
+        cursor: Option<EventID>,
+        only_new: bool,
+    ) -> Result<usize> {
+        let mut history = History::new(&self.rpc, queries, cursor);
+        let mut count = 0;
+        while let Some((endpoint, envelope)) = history.next().await? {
+            if !self.is_contract_event(&envelope.event) {
+                continue;
+            }
+            let applied = if only_new {
+                self.apply_new(&envelope, endpoint).await
+            } else {
+                self.apply(&envelope, endpoint).await
+            };
+            if !applied {
+                return Err(eyre!("Backend or verifying endpoints are unavailable"));
+            }
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn is_contract_event(&self, event: &SuiEvent) -> bool {
+        matches!(event, SuiEvent::MoveEvent { package_id, .. } if *package_id == self.package)
+    }
+}
@@ -0,0 +1,552 @@
+use backend::config::{AppConfig, Config as BackendAppConfig, DatabaseConfig, GraphQlConfig};
+use backend::startup::App;
+use indexer::config::{
+    AdminConfig, BackendConfig, BufferConfig, Config, DatabaseConfig as IndexerDatabaseConfig,
+    HaConfig, IngestionConfig, ScriptConfig, SuiContractConfig, SuiJsonRpcConfig,
+    SuiRpcEndpointConfig,
+};
+use indexer::startup::Indexer;
+use models::events::EventDecoder;
+use models::move_events::encode_bcs;
+use models::sui_sdk::rpc_types::{
+    EventID, SuiEvent, SuiEventEnvelope, SuiMoveStruct, SuiMoveValue,
+};
+use models::sui_sdk::types::base_types::{ObjectID, SuiAddress, TransactionDigest};
+use models::sui_sdk::types::Identifier;
+use models::Network;
+use serde_json::{json, Value};
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::collections::BTreeMap;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use sui_mock::MockSuiNode;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+pub const API_VERSION: &str = "0.27.0";
+pub const PACKAGE: &str = "0x9b5b3f8e3fd1c6aaf2e8f5a4e1f5bd2c1d2e7f40";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct TestIndexer {
+    pub nodes: Vec<MockSuiNode>,
+    pub db_pool: PgPool,
+    backend_config: BackendAppConfig,
+    config: Config,
+    indexer: JoinHandle<eyre::Result<()>>,
+    admin_port: Option<u16>,
+    package: ObjectID,
+    tx_seq: AtomicI64,
+}
+
+#[derive(Debug)]
+pub struct NftRow {
+    pub owner: String,
+    pub r#type: String,
+    pub attached_to: Option<String>,
+}
+
+impl TestIndexer {
+    pub fn node(&self) -> &MockSuiNode {
+        &self.nodes[0]
+    }
+
+    pub async fn start_backend(&self) {
+        let backend = App::build(self.backend_config.clone())
+            .await
+            .expect("Failed to build backend");
+        tokio::spawn(backend.run_until_stopped());
+    }
+
+    /// Stop the indexer abruptly, as if its process died.
+    pub fn kill(&self) {
+        self.indexer.abort();
+    }
+
+    /// Start another mocked node and an indexer of the network reading from it, which shares
+    /// the backend with this one.
+    pub async fn spawn_on_network(
+        &self,
+        network: Network,
+    ) -> (MockSuiNode, JoinHandle<eyre::Result<()>>) {
+        let node = MockSuiNode::start(API_VERSION).await;
+        let mut config = self.config.clone();
+        config.network = network;
+        config.sui_json_rpc.endpoints = vec![SuiRpcEndpointConfig {
+            http_url: node.http_url(),
+            ws_url: node.ws_url(),
+        }];
+        let indexer = Indexer::build(config)
+            .await
+            .expect("Failed to build indexer");
+        let indexer = tokio::spawn(indexer.run_until_stopped());
+        assert!(
+            node.wait_for_subscribers(1, TIMEOUT).await,
+            "Indexer didn't subscribe to events"
+        );
+
+        (node, indexer)
+    }
+
+    /// Start another indexer with the same config.
+    pub async fn spawn_replica(&self) -> JoinHandle<eyre::Result<()>> {
+        let indexer = Indexer::build(self.config.clone())
+            .await
+            .expect("Failed to build indexer");
+        tokio::spawn(indexer.run_until_stopped())
+    }
+
+    pub fn lemon_created(&self, id: SuiAddress, owner: SuiAddress) -> SuiEventEnvelope {
+        self.envelope(owner, "lemon", "LemonCreated", nft_fields(id))
+    }
+
+    /// `LemonCreated` event declared in another module of the contract's package.
+    pub fn lemon_created_in(
+        &self,
+        module: &str,
+        id: SuiAddress,
+        owner: SuiAddress,
+    ) -> SuiEventEnvelope {
+        self.envelope(owner, module, "LemonCreated", nft_fields(id))
+    }
+
+    /// Lemon with traits given as `(name, flavour)` pairs of Move values.
+    pub fn lemon_created_with_traits(
+        &self,
+        id: SuiAddress,
+        owner: SuiAddress,
+        traits: Vec<(&str, SuiMoveValue)>,
+    ) -> SuiEventEnvelope {
+        let fields = nft_fields_with_traits(id, traits);
+        self.envelope(owner, "lemon", "LemonCreated", fields)
+    }
+
+    pub fn item_created(&self, id: SuiAddress, owner: SuiAddress) -> SuiEventEnvelope {
+        self.envelope(owner, "item", "ItemCreated", nft_fields(id))
+    }
+
+    /// Item with the `slot` trait set to `slot`.
+    pub fn item_created_in_slot(
+        &self,
+        id: SuiAddress,
+        owner: SuiAddress,
+        slot: &str,
+    ) -> SuiEventEnvelope {
+        let traits = vec![("slot", SuiMoveValue::String(slot.to_string()))];
+        let fields = nft_fields_with_traits(id, traits);
+        self.envelope(owner, "item", "ItemCreated", fields)
+    }
+
+    pub fn item_added(&self, lemon_id: SuiAddress, item_id: SuiAddress) -> SuiEventEnvelope {
+        let fields = BTreeMap::from([
+            ("lemon_id".to_string(), SuiMoveValue::Address(lemon_id)),
+            ("item_id".to_string(), SuiMoveValue::Address(item_id)),
+        ]);
+        self.envelope(
+            SuiAddress::random_for_testing_only(),
+            "lemon",
+            "ItemAdded",
+            fields,
+        )
+    }
+
+    fn envelope(
+        &self,
+        sender: SuiAddress,
+        module: &str,
+        name: &str,
+        fields: BTreeMap<String, SuiMoveValue>,
+    ) -> SuiEventEnvelope {
+        let tx_seq = self.tx_seq.fetch_add(1, Ordering::Relaxed);
+        // Fields which don't match the Move types, e.g. non-string flavours, have no payload.
+        let bcs = encode_bcs(name, fields.clone()).unwrap_or_default();
+        SuiEventEnvelope {
+            timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            tx_digest: TransactionDigest::random(),
+            id: EventID {
+                tx_seq,
+                event_seq: 0,
+            },
+            event: SuiEvent::MoveEvent {
+                package_id: self.package,
+                transaction_module: Identifier::new(module).unwrap(),
+                sender,
+                type_: format!("{}::{module}::{name}", self.package),
+                fields: Some(SuiMoveStruct::WithFields(fields)),
+                bcs,
+            },
+        }
+    }
+
+    /// Send a GraphQL query to the backend and return the whole response.
+    pub async fn graphql(&self, query: &str) -> Value {
+        let BackendAppConfig { app, .. } = &self.backend_config;
+        reqwest::Client::new()
+            .post(format!("http://{}:{}/graphql", app.host, app.port))
+            .json(&json!({ "query": query }))
+            .send()
+            .await
+            .expect("Failed to send GraphQL query")
+            .json()
+            .await
+            .expect("Failed to parse GraphQL response")
+    }
+
+    /// Poll the GraphQL query until its response matches the predicate.
+    pub async fn wait_for_graphql<F>(&self, query: &str, predicate: F) -> Value
+    where
+        F: Fn(&Value) -> bool,
+    {
+        let poll = async {
+            loop {
+                let resp = self.graphql(query).await;
+                if predicate(&resp) {
+                    return resp;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+
+        tokio::time::timeout(TIMEOUT, poll)
+            .await
+            .unwrap_or_else(|_| panic!("`{query}` didn't match in time"))
+    }
+
+    /// Send a POST request to the admin API, which must be enabled, and return the response.
+    pub async fn admin_post(&self, path: &str, body: Value) -> reqwest::Response {
+        let port = self.admin_port.expect("Admin API is disabled");
+        reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{port}{path}"))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to send admin request")
+    }
+
+    /// Poll the admin API's status until it matches the predicate.
+    pub async fn wait_for_status<F>(&self, predicate: F) -> Value
+    where
+        F: Fn(&Value) -> bool,
+    {
+        let port = self.admin_port.expect("Admin API is disabled");
+        let poll = async {
+            loop {
+                let status: Value = reqwest::get(format!("http://127.0.0.1:{port}/status"))
+                    .await
+                    .expect("Failed to get status")
+                    .json()
+                    .await
+                    .expect("Failed to parse status");
+                if predicate(&status) {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        };
+
+        tokio::time::timeout(TIMEOUT, poll)
+            .await
+            .expect("Status didn't match in time")
+    }
+
+    /// Poll the database until the nft shows up and matches the predicate.
+    pub async fn wait_for_nft<F>(&self, id: SuiAddress, predicate: F) -> NftRow
+    where
+        F: Fn(&NftRow) -> bool,
+    {
+        let id = id.to_string();
+        let poll = async {
+            loop {
+                let row = sqlx::query_as!(
+                    NftRow,
+                    r#"SELECT owner, type as "type", attached_to FROM nfts WHERE id = $1"#,
+                    id
+                )
+                .fetch_optional(&self.db_pool)
+                .await
+                .expect("Failed to query nfts");
+
+                match row {
+                    Some(row) if predicate(&row) => return row,
+                    _ => tokio::time::sleep(Duration::from_millis(100)).await,
+                }
+            }
+        };
+
+        tokio::time::timeout(TIMEOUT, poll)
+            .await
+            .unwrap_or_else(|_| panic!("Nft `{id}` wasn't indexed in time"))
+    }
+}
+
+fn nft_fields(id: SuiAddress) -> BTreeMap<String, SuiMoveValue> {
+    let url = format!("https://battlemon.com/nfts/{id}.png");
+    BTreeMap::from([
+        ("id".to_string(), SuiMoveValue::Address(id)),
+        ("url".to_string(), SuiMoveValue::String(url)),
+        ("traits".to_string(), SuiMoveValue::Vector(Vec::new())),
+    ])
+}
+
+fn nft_fields_with_traits(
+    id: SuiAddress,
+    traits: Vec<(&str, SuiMoveValue)>,
+) -> BTreeMap<String, SuiMoveValue> {
+    let traits = traits
+        .into_iter()
+        .map(|(name, flavour)| {
+            SuiMoveValue::Struct(SuiMoveStruct::WithFields(BTreeMap::from([
+                ("name".to_string(), SuiMoveValue::String(name.to_string())),
+                ("flavour".to_string(), flavour),
+            ])))
+        })
+        .collect();
+    let mut fields = nft_fields(id);
+    fields.insert("traits".to_string(), SuiMoveValue::Vector(traits));
+    fields
+}
+
+/// Spawn a backend with a fresh database and an indexer which reads from the mocked nodes.
+pub async fn spawn_indexer(nodes: usize) -> TestIndexer {
+    spawn(nodes, true, |_, _| {}).await
+}
+
+/// Spawn an indexer which decodes events with `decoder`.
+pub async fn spawn_indexer_with_decoder(nodes: usize, decoder: EventDecoder) -> TestIndexer {
+    spawn(nodes, true, |config, _| config.event_decoder = decoder).await
+}
+
+/// Spawn an indexer with the on-disk buffer, the backend isn't running until
+/// [`TestIndexer::start_backend`] is called.
+pub async fn spawn_indexer_with_backend_down(nodes: usize) -> TestIndexer {
+    spawn(nodes, false, |_, _| {}).await
+}
+
+/// Spawn an indexer whose on-disk buffer holds `max_bytes` at most, the backend is down
+/// like with [`spawn_indexer_with_backend_down`].
+pub async fn spawn_indexer_with_small_buffer(nodes: usize, max_bytes: u64) -> TestIndexer {
+    spawn(nodes, false, |config, _| {
+        if let Some(buffer) = config.buffer.as_mut() {
+            buffer.max_bytes = max_bytes;
+        }
+    })
+    .await
+}
+
+/// Spawn an indexer which verifies events against another node, with the on-disk buffer to
+/// retry the events which couldn't be verified and the admin API to watch its progress.
+pub async fn spawn_indexer_verifying_events(nodes: usize) -> TestIndexer {
+    let app = spawn(nodes, false, |config, _| {
+        config.sui_json_rpc.verify_events = true;
+        config.admin = Some(admin_config());
+    })
+    .await;
+    app.start_backend().await;
+    app
+}
+
+/// Spawn an indexer with the admin API on a random port.
+pub async fn spawn_indexer_with_admin(nodes: usize) -> TestIndexer {
+    spawn(nodes, true, |config, _| config.admin = Some(admin_config())).await
+}
+
+/// Spawn an indexer in the dry run mode, with the admin API to watch its progress.
+pub async fn spawn_indexer_in_dry_run(nodes: usize) -> TestIndexer {
+    spawn(nodes, true, |config, _| {
+        config.dry_run = true;
+        config.admin = Some(admin_config());
+    })
+    .await
+}
+
+/// Spawn the leader of indexer replicas sharing the cursor in the test database, more
+/// replicas are started with [`TestIndexer::spawn_replica`].
+pub async fn spawn_indexer_with_ha(nodes: usize) -> TestIndexer {
+    spawn(nodes, true, |config, db| {
+        config.ha = Some(HaConfig {
+            db: IndexerDatabaseConfig {
+                host: db.host.clone(),
+                port: db.port,
+                username: db.username.clone(),
+                password: db.password.clone(),
+                db_name: db.db_name.clone(),
+            },
+            lock_id: 1,
+            cursor_name: None,
+            retry_interval_secs: 1,
+            heartbeat_interval_secs: 1,
+        });
+    })
+    .await
+}
+
+/// Spawn an indexer which walks checkpoints from genesis instead of subscribing, keeping
+/// the last indexed one in a fresh file. The admin API reports its progress.
+pub async fn spawn_indexer_walking_checkpoints(nodes: usize) -> TestIndexer {
+    spawn(nodes, true, |config, _| {
+        config.admin = Some(admin_config());
+        config.ingestion = IngestionConfig::Checkpoints {
+            start_checkpoint: 0,
+            poll_interval_ms: 100,
+            checkpoint_file: std::env::temp_dir()
+                .join(format!("indexer-checkpoint-{}", Uuid::new_v4())),
+        };
+    })
+    .await
+}
+
+fn admin_config() -> AdminConfig {
+    AdminConfig {
+        host: "127.0.0.1".into(),
+        port: 0,
+    }
+}
+
+/// Spawn an indexer which handles events of the contract's `module::name` type with the
+/// Rhai script.
+pub async fn spawn_indexer_with_script(nodes: usize, event: &str, script: &str) -> TestIndexer {
+    spawn_indexer_with_script_for(nodes, format!("{PACKAGE}::{event}"), script).await
+}
+
+/// Spawn an indexer which handles events of the fully qualified type, spelled as configured,
+/// with the Rhai script.
+pub async fn spawn_indexer_with_script_for(
+    nodes: usize,
+    event_type: String,
+    script: &str,
+) -> TestIndexer {
+    let path = std::env::temp_dir().join(format!("indexer-script-{}.rhai", Uuid::new_v4()));
+    std::fs::write(&path, script).expect("Failed to write script");
+    spawn(nodes, true, |config, _| {
+        config.scripting.scripts = vec![ScriptConfig { event_type, path }];
+    })
+    .await
+}
+
+async fn spawn<F>(nodes: usize, backend_up: bool, configure: F) -> TestIndexer
+where
+    F: FnOnce(&mut Config, &DatabaseConfig),
+{
+    let mut mocks = Vec::new();
+    for _ in 0..nodes {
+        mocks.push(MockSuiNode::start(API_VERSION).await);
+    }
+
+    let db_config = DatabaseConfig {
+        host: std::env::var("POSTGRES_HOST").unwrap_or_else(|_| "localhost".into()),
+        port: std::env::var("POSTGRES_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(5432),
+        username: std::env::var("POSTGRES_USER").unwrap_or_else(|_| "postgres".into()),
+        password: std::env::var("POSTGRES_PASSWORD").unwrap_or_else(|_| "password".into()),
+        db_name: Uuid::new_v4().to_string(),
+    };
+    let db_pool = configure_database(&db_config).await;
+    // The port is reserved upfront, so the indexer knows it before the backend is started.
+    let backend_port = TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("Failed to reserve backend port")
+        .port();
+
+    let backend_config = BackendAppConfig {
+        app: AppConfig {
+            host: "127.0.0.1".into(),
+            port: backend_port,
+        },
+        db: db_config,
+        graphql: GraphQlConfig {
+            playground_route: "/graphql/playground".into(),
+            default_network: Network::Localnet,
+        },
+    };
+    let mut config = Config {
+        network: Network::Localnet,
+        sui_json_rpc: SuiJsonRpcConfig {
+            endpoints: mocks
+                .iter()
+                .map(|node| SuiRpcEndpointConfig {
+                    http_url: node.http_url(),
+                    ws_url: node.ws_url(),
+                })
+                .collect(),
+            verify_events: false,
+            failover_delay_secs: 1,
+        },
+        sui_contract: SuiContractConfig {
+            address: PACKAGE.into(),
+            additional_packages: Vec::new(),
+        },
+        backend: BackendConfig {
+            host: "127.0.0.1".into(),
+            port: backend_port,
+        },
+        admin: None,
+        scripting: Default::default(),
+        dry_run: false,
+        parse_mode: Default::default(),
+        event_decoder: EventDecoder::default(),
+        ha: None,
+        buffer: (!backend_up).then(|| BufferConfig {
+            path: std::env::temp_dir().join(format!("indexer-buffer-{}", Uuid::new_v4())),
+            max_bytes: 1024 * 1024,
+            alert_ratio: 0.8,
+            retry_interval_secs: 1,
+            max_retry_interval_secs: 1,
+        }),
+        ingestion: Default::default(),
+    };
+    configure(&mut config, &backend_config.db);
+    let indexer = Indexer::build(config.clone())
+        .await
+        .expect("Failed to build indexer");
+    let admin_port = indexer.admin_port();
+    let indexer = tokio::spawn(indexer.run_until_stopped());
+
+    if let IngestionConfig::Subscription = config.ingestion {
+        let subscribed = futures::future::select_all(
+            mocks
+                .iter()
+                .map(|node| Box::pin(node.wait_for_subscribers(1, TIMEOUT))),
+        )
+        .await
+        .0;
+        assert!(subscribed, "Indexer didn't subscribe to events");
+    }
+
+    let app = TestIndexer {
+        nodes: mocks,
+        db_pool,
+        backend_config,
+        config,
+        indexer,
+        admin_port,
+        package: ObjectID::from_hex_literal(PACKAGE).unwrap(),
+        tx_seq: AtomicI64::new(1),
+    };
+    if backend_up {
+        app.start_backend().await;
+    }
+
+    app
+}
+
+async fn configure_database(config: &DatabaseConfig) -> PgPool {
+    let mut connection = PgConnection::connect_with(&config.without_db())
+        .await
+        .expect("Failed to connect to Postgres");
+    connection
+        .execute(format!(r#"CREATE DATABASE "{}";"#, config.db_name).as_str())
+        .await
+        .expect("Failed to create database");
+
+    let db_pool = PgPool::connect_with(config.with_db())
+        .await
+        .expect("Failed to connect to Postgres");
+    sqlx::migrate!("../backend/migrations")
+        .run(&db_pool)
+        .await
+        .expect("Failed to migrate the database");
+
+    db_pool
+}
@@ -0,0 +1,1075 @@
+use crate::helpers::{
+    spawn_indexer, spawn_indexer_in_dry_run, spawn_indexer_verifying_events,
+    spawn_indexer_walking_checkpoints, spawn_indexer_with_admin, spawn_indexer_with_backend_down,
+    spawn_indexer_with_decoder, spawn_indexer_with_ha, spawn_indexer_with_script,
+    spawn_indexer_with_script_for, spawn_indexer_with_small_buffer, PACKAGE,
+};
+use models::events::EventDecoder;
+use models::sui_sdk::rpc_types::{EventID, SuiEvent, SuiMoveStruct, SuiMoveValue};
+use models::sui_sdk::types::base_types::SuiAddress;
+use models::Network;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+use sui_mock::transaction_response;
+
+mod helpers;
+
+#[tokio::test]
+async fn lemon_created_event_is_stored() {
+    let app = spawn_indexer(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+
+    let nft = app.wait_for_nft(lemon, |_| true).await;
+    assert_eq!(nft.owner, owner.to_string());
+    assert_eq!(nft.r#type, "lemon");
+}
+
+#[tokio::test]
+async fn nfts_are_found_by_non_canonical_owner() {
+    let app = spawn_indexer(1).await;
+    let lemon = SuiAddress::random_for_testing_only();
+    let owner = SuiAddress::from_str("0x0000000000000000000000000000000000c0ffee").unwrap();
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let resp = app
+        .graphql(r#"{ nfts(owner: "0XC0FFEE") { nodes { id owner } } }"#)
+        .await;
+    let nodes = &resp["data"]["nfts"]["nodes"];
+    assert_eq!(nodes[0]["id"], lemon.to_string());
+    assert_eq!(nodes[0]["owner"], owner.to_string());
+
+    let resp = app
+        .graphql(r#"{ nfts(owner: "c0ffee-lemon") { nodes { id } } }"#)
+        .await;
+    let error = resp["errors"][0]["message"].as_str().unwrap();
+    assert!(error.contains("is not a valid Sui address"), "{error}");
+}
+
+#[tokio::test]
+async fn nfts_are_paged_through_with_cursors() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let lemons: Vec<_> = (0..3)
+        .map(|_| SuiAddress::random_for_testing_only())
+        .collect();
+    for &lemon in &lemons {
+        app.node().emit(&app.lemon_created(lemon, owner));
+    }
+    for &lemon in &lemons {
+        app.wait_for_nft(lemon, |_| true).await;
+    }
+
+    let page = |args: String| {
+        let app = &app;
+        async move {
+            let query = format!(
+                r#"{{ nfts(owner: "{owner}", {args}) {{ totalCount nodes {{ id }} pageInfo {{ hasPreviousPage hasNextPage startCursor endCursor }} }} }}"#
+            );
+            let resp = app.graphql(&query).await;
+            resp["data"]["nfts"].clone()
+        }
+    };
+    let ids = |page: &serde_json::Value| -> Vec<String> {
+        page["nodes"]
+            .as_array()
+            .unwrap_or_else(|| panic!("{page}"))
+            .iter()
+            .map(|nft| nft["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let first = page("first: 2".to_string()).await;
+    assert_eq!(first["totalCount"], 3);
+    assert_eq!(first["pageInfo"]["hasPreviousPage"], false);
+    assert_eq!(first["pageInfo"]["hasNextPage"], true);
+    let end_cursor = &first["pageInfo"]["endCursor"];
+    let second = page(format!("first: 2, after: {end_cursor}")).await;
+    assert_eq!(second["pageInfo"]["hasPreviousPage"], true);
+    assert_eq!(second["pageInfo"]["hasNextPage"], false);
+    let mut all = ids(&first);
+    all.extend(ids(&second));
+    let mut expected: Vec<_> = lemons.iter().map(ToString::to_string).collect();
+    expected.sort();
+    let mut sorted = all.clone();
+    sorted.sort();
+    assert_eq!(sorted, expected);
+
+    let last = page("last: 1".to_string()).await;
+    assert_eq!(ids(&last), vec![all[2].clone()]);
+    assert_eq!(last["pageInfo"]["hasPreviousPage"], true);
+    let start_cursor = &last["pageInfo"]["startCursor"];
+    let before = page(format!("last: 2, before: {start_cursor}")).await;
+    assert_eq!(ids(&before), all[..2]);
+
+    let query = format!(r#"{{ nfts(owner: "{owner}", first: 1, last: 1) {{ nodes {{ id }} }} }}"#);
+    let resp = app.graphql(&query).await;
+    assert_eq!(resp["data"], serde_json::Value::Null, "{resp}");
+    let error = resp["errors"][0]["message"].as_str().unwrap();
+    assert!(error.contains("cannot exist at the same time"), "{error}");
+
+    let query = format!(r#"{{ nft(id: "{}") {{ id globalId }} }}"#, all[0]);
+    let nft = app.graphql(&query).await["data"]["nft"].clone();
+    assert_eq!(nft["id"], all[0]);
+    let global_id = &nft["globalId"];
+    assert_ne!(global_id, &nft["id"]);
+    let query =
+        format!(r#"{{ node(globalId: {global_id}) {{ globalId ... on Nft {{ id owner }} }} }}"#);
+    let resp = app.graphql(&query).await;
+    assert_eq!(&resp["data"]["node"]["globalId"], global_id);
+    assert_eq!(resp["data"]["node"]["id"], all[0]);
+    assert_eq!(resp["data"]["node"]["owner"], owner.to_string());
+}
+
+#[tokio::test]
+async fn nfts_are_filtered_by_traits_ids_and_attachment() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (cowboy, pirate, plain, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let cap = |flavour: &str| vec![("cap", SuiMoveValue::String(flavour.into()))];
+
+    app.node()
+        .emit(&app.lemon_created_with_traits(cowboy, owner, cap("cowboy")));
+    app.node()
+        .emit(&app.lemon_created_with_traits(pirate, owner, cap("pirate")));
+    app.node().emit(&app.lemon_created(plain, owner));
+    app.node().emit(&app.item_created(item, owner));
+    app.wait_for_nft(item, |_| true).await;
+    app.node().emit(&app.item_added(cowboy, item));
+    app.wait_for_nft(item, |nft| nft.attached_to.is_some())
+        .await;
+
+    let ids = |filter: String| {
+        let app = &app;
+        async move {
+            let query = format!(
+                r#"{{ nfts(owner: "{owner}", indirect: true, filter: {filter}) {{ nodes {{ id }} }} }}"#
+            );
+            let resp = app.graphql(&query).await;
+            let mut ids: Vec<String> = resp["data"]["nfts"]["nodes"]
+                .as_array()
+                .unwrap_or_else(|| panic!("{resp}"))
+                .iter()
+                .map(|nft| nft["id"].as_str().unwrap().to_string())
+                .collect();
+            ids.sort();
+            ids
+        }
+    };
+    let sorted = |nfts: &[SuiAddress]| {
+        let mut ids: Vec<_> = nfts.iter().map(ToString::to_string).collect();
+        ids.sort();
+        ids
+    };
+
+    let filter =
+        r#"{ traits: [{ name: "cap", flavours: [{ string: "cowboy" }, { string: "ninja" }] }] }"#;
+    assert_eq!(ids(filter.to_string()).await, sorted(&[cowboy]));
+    let filter = r#"{ traits: [{ name: "cap" }] }"#;
+    assert_eq!(ids(filter.to_string()).await, sorted(&[cowboy, pirate]));
+    let filter = format!(r#"{{ ids: ["{pirate}", "{plain}"] }}"#);
+    assert_eq!(ids(filter).await, sorted(&[pirate, plain]));
+    let filter = format!(r#"{{ attachedTo: "{cowboy}" }}"#);
+    assert_eq!(ids(filter).await, sorted(&[item]));
+    let filter = r#"{ unattached: true }"#;
+    assert_eq!(
+        ids(filter.to_string()).await,
+        sorted(&[cowboy, pirate, plain])
+    );
+}
+
+#[tokio::test]
+async fn nfts_are_ordered_by_numeric_trait_values() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let lemons: Vec<_> = (0..4)
+        .map(|_| SuiAddress::random_for_testing_only())
+        .collect();
+    for (&lemon, level) in lemons.iter().zip([9, 10, 100]) {
+        let traits = vec![("level", SuiMoveValue::Number(level))];
+        app.node()
+            .emit(&app.lemon_created_with_traits(lemon, owner, traits));
+    }
+    app.node().emit(&app.lemon_created(lemons[3], owner));
+    for &lemon in &lemons {
+        app.wait_for_nft(lemon, |_| true).await;
+    }
+
+    let page = |args: String| {
+        let app = &app;
+        async move {
+            let query = format!(
+                r#"{{ nfts(owner: "{owner}", {args}) {{ nodes {{ id }} pageInfo {{ endCursor }} }} }}"#
+            );
+            let resp = app.graphql(&query).await;
+            let ids: Vec<String> = resp["data"]["nfts"]["nodes"]
+                .as_array()
+                .unwrap_or_else(|| panic!("{resp}"))
+                .iter()
+                .map(|nft| nft["id"].as_str().unwrap().to_string())
+                .collect();
+            (ids, resp["data"]["nfts"]["pageInfo"]["endCursor"].clone())
+        }
+    };
+    let ids =
+        |idx: &[usize]| -> Vec<String> { idx.iter().map(|&i| lemons[i].to_string()).collect() };
+
+    let order = r#"orderBy: { field: TRAIT, trait: "level" }"#;
+    let (asc, _) = page(order.to_string()).await;
+    assert_eq!(asc, ids(&[0, 1, 2, 3]));
+
+    let order = r#"orderBy: { field: TRAIT, trait: "level", direction: DESC }"#;
+    let (first, cursor) = page(format!("{order}, first: 2")).await;
+    assert_eq!(first, ids(&[3, 2]));
+    let (second, _) = page(format!("{order}, first: 2, after: {cursor}")).await;
+    assert_eq!(second, ids(&[1, 0]));
+}
+
+#[tokio::test]
+async fn nfts_are_ordered_and_paged_by_each_field() {
+    let app = spawn_indexer(1).await;
+    let owners: Vec<_> = (0..3)
+        .map(|_| SuiAddress::random_for_testing_only())
+        .collect();
+    let lemons: Vec<_> = (0..3)
+        .map(|_| SuiAddress::random_for_testing_only())
+        .collect();
+    // Lemons are created in order, a few milliseconds apart, with 2, 1 and 0 items.
+    for (&lemon, &owner) in lemons.iter().zip(&owners) {
+        app.node().emit(&app.lemon_created(lemon, owner));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    for (&lemon, item_count) in lemons.iter().zip([2, 1]) {
+        for _ in 0..item_count {
+            let item = SuiAddress::random_for_testing_only();
+            app.node().emit(&app.item_created(item, owners[0]));
+            app.node().emit(&app.item_added(lemon, item));
+            app.wait_for_nft(item, |row| row.attached_to.is_some())
+                .await;
+        }
+    }
+    for &lemon in &lemons {
+        app.wait_for_nft(lemon, |_| true).await;
+    }
+
+    let page = |args: String| {
+        let app = &app;
+        async move {
+            let query = format!(
+                r#"{{ nfts(type: LEMON, {args}) {{ nodes {{ id }} pageInfo {{ startCursor endCursor }} }} }}"#
+            );
+            let resp = app.graphql(&query).await;
+            let ids: Vec<String> = resp["data"]["nfts"]["nodes"]
+                .as_array()
+                .unwrap_or_else(|| panic!("{resp}"))
+                .iter()
+                .map(|nft| nft["id"].as_str().unwrap().to_string())
+                .collect();
+            let page_info = &resp["data"]["nfts"]["pageInfo"];
+            (
+                ids,
+                page_info["startCursor"].clone(),
+                page_info["endCursor"].clone(),
+            )
+        }
+    };
+    let ids =
+        |idx: &[usize]| -> Vec<String> { idx.iter().map(|&i| lemons[i].to_string()).collect() };
+    let mut by_owner = vec![0, 1, 2];
+    by_owner.sort_by_key(|&i| owners[i].to_string());
+
+    for (field, ascending) in [
+        ("CREATED_AT", vec![0, 1, 2]),
+        ("OWNER", by_owner),
+        ("ITEM_COUNT", vec![2, 1, 0]),
+    ] {
+        let descending: Vec<_> = ascending.iter().rev().copied().collect();
+        let asc = format!("orderBy: {{ field: {field} }}");
+        let desc = format!("orderBy: {{ field: {field}, direction: DESC }}");
+
+        let (all, _, _) = page(asc.clone()).await;
+        assert_eq!(all, ids(&ascending), "{field}");
+        let (first, _, cursor) = page(format!("{asc}, first: 1")).await;
+        assert_eq!(first, ids(&ascending[..1]), "{field}");
+        let (rest, _, _) = page(format!("{asc}, first: 2, after: {cursor}")).await;
+        assert_eq!(rest, ids(&ascending[1..]), "{field}");
+
+        let (all, _, _) = page(desc.clone()).await;
+        assert_eq!(all, ids(&descending), "{field}");
+        let (first, _, cursor) = page(format!("{desc}, first: 2")).await;
+        assert_eq!(first, ids(&descending[..2]), "{field}");
+        let (rest, _, _) = page(format!("{desc}, first: 2, after: {cursor}")).await;
+        assert_eq!(rest, ids(&descending[2..]), "{field}");
+        let (last, cursor, _) = page(format!("{desc}, last: 1")).await;
+        assert_eq!(last, ids(&descending[2..]), "{field}");
+        let (before, _, _) = page(format!("{desc}, last: 2, before: {cursor}")).await;
+        assert_eq!(before, ids(&descending[..2]), "{field}");
+    }
+
+    let (_, _, cursor) = page("orderBy: { field: CREATED_AT }, first: 1".to_string()).await;
+    let query = format!(
+        r#"{{ nfts(orderBy: {{ field: CREATED_AT, direction: DESC }}, after: {cursor}) {{ nodes {{ id }} }} }}"#
+    );
+    let resp = app.graphql(&query).await;
+    let error = resp["errors"][0]["message"].as_str().unwrap();
+    assert!(error.contains("another order"), "{error}");
+}
+
+#[tokio::test]
+async fn trait_values_keep_their_types() {
+    let app = spawn_indexer(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let stats = SuiMoveStruct::WithFields(BTreeMap::from([(
+        "speed".to_string(),
+        SuiMoveValue::Number(7),
+    )]));
+    let traits = vec![
+        ("cap", SuiMoveValue::String("cowboy".into())),
+        ("level", SuiMoveValue::Number(42)),
+        ("shiny", SuiMoveValue::Bool(true)),
+        ("stats", SuiMoveValue::Struct(stats)),
+    ];
+
+    app.node()
+        .emit(&app.lemon_created_with_traits(lemon, owner, traits));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let query = format!(
+        r#"{{ nft(id: "{lemon}") {{
+            traits {{ name flavour {{ kind string number bool nested {{ name flavour {{ number }} }} }} }}
+        }} }}"#
+    );
+    let resp = app.graphql(&query).await;
+    let traits = &resp["data"]["nft"]["traits"];
+    assert_eq!(traits[0]["flavour"]["kind"], "STRING");
+    assert_eq!(traits[0]["flavour"]["string"], "cowboy");
+    assert_eq!(traits[1]["flavour"]["kind"], "NUMBER");
+    assert_eq!(traits[1]["flavour"]["number"], 42);
+    assert_eq!(traits[2]["flavour"]["bool"], true);
+    assert_eq!(traits[3]["flavour"]["kind"], "NESTED");
+    assert_eq!(traits[3]["flavour"]["nested"][0]["name"], "speed");
+    assert_eq!(traits[3]["flavour"]["nested"][0]["flavour"]["number"], 7);
+}
+
+#[tokio::test]
+async fn malformed_traits_are_skipped_in_lenient_mode() {
+    let app = spawn_indexer(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let traits = vec![
+        ("cap", SuiMoveValue::String("cowboy".into())),
+        ("hat", SuiMoveValue::Option(Box::new(None))),
+    ];
+
+    app.node()
+        .emit(&app.lemon_created_with_traits(lemon, owner, traits));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let query = format!(r#"{{ nft(id: "{lemon}") {{ traits {{ name }} }} }}"#);
+    let resp = app.graphql(&query).await;
+    let traits = resp["data"]["nft"]["traits"].as_array().unwrap();
+    assert_eq!(traits.len(), 1);
+    assert_eq!(traits[0]["name"], "cap");
+}
+
+#[tokio::test]
+async fn traits_declared_by_the_kind_must_have_its_type() {
+    let app = spawn_indexer(1).await;
+    let (lemon, item, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let traits = vec![
+        ("cap", SuiMoveValue::Number(3)),
+        ("level", SuiMoveValue::Number(42)),
+    ];
+
+    app.node()
+        .emit(&app.lemon_created_with_traits(lemon, owner, traits));
+    app.node().emit(&app.item_created(item, owner));
+    app.wait_for_nft(lemon, |_| true).await;
+    app.wait_for_nft(item, |_| true).await;
+
+    let query = format!(
+        r#"{{ nfts(owner: "{owner}", type: LEMON) {{ nodes {{ id type traits {{ name }} }} }} }}"#
+    );
+    let resp = app.graphql(&query).await;
+    let nfts = resp["data"]["nfts"]["nodes"].as_array().unwrap();
+    assert_eq!(nfts.len(), 1, "{resp}");
+    assert_eq!(nfts[0]["id"], lemon.to_string());
+    assert_eq!(nfts[0]["type"], "LEMON");
+    let traits = nfts[0]["traits"].as_array().unwrap();
+    assert_eq!(traits.len(), 1);
+    assert_eq!(traits[0]["name"], "level");
+}
+
+#[tokio::test]
+async fn events_of_other_modules_are_not_parsed_as_ours() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (imposter, lemon) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node()
+        .emit(&app.lemon_created_in("imposter", imposter, owner));
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let resp = app
+        .graphql(&format!(r#"{{ nft(id: "{imposter}") {{ id }} }}"#))
+        .await;
+    assert!(resp["data"]["nft"].is_null(), "{resp}");
+}
+
+#[tokio::test]
+async fn unknown_events_are_stored_and_counted() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (unknown, lemon) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node()
+        .emit(&app.lemon_created_in("lemon_v2", unknown, owner));
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let resp = app
+        .graphql("{ unknownEventCounts { type count } unknownEvents { sender fields } }")
+        .await;
+    let counts = &resp["data"]["unknownEventCounts"];
+    assert_eq!(
+        counts[0]["type"],
+        format!("{}::lemon_v2::LemonCreated", helpers::PACKAGE)
+    );
+    assert_eq!(counts[0]["count"], 1);
+    let events = &resp["data"]["unknownEvents"];
+    assert_eq!(events[0]["sender"], owner.to_string());
+    assert_eq!(events[0]["fields"]["id"], unknown.to_string());
+}
+
+#[tokio::test]
+async fn events_are_decoded_from_bcs_payload() {
+    let app = spawn_indexer_with_decoder(1, EventDecoder::Bcs).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let traits = vec![("cap", SuiMoveValue::String("cowboy".into()))];
+    let mut envelope = app.lemon_created_with_traits(lemon, owner, traits);
+    // Only the BCS payload is left to decode the event from.
+    if let SuiEvent::MoveEvent { fields, .. } = &mut envelope.event {
+        *fields = None;
+    }
+
+    app.node().emit(&envelope);
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let query =
+        format!(r#"{{ nft(id: "{lemon}") {{ owner traits {{ name flavour {{ string }} }} }} }}"#);
+    let resp = app.graphql(&query).await;
+    let nft = &resp["data"]["nft"];
+    assert_eq!(nft["owner"], owner.to_string());
+    assert_eq!(nft["traits"][0]["name"], "cap");
+    assert_eq!(nft["traits"][0]["flavour"]["string"], "cowboy");
+}
+
+#[tokio::test]
+async fn scripts_match_any_spelling_of_the_event_type() {
+    let script = r#"#{ op: "upsert", id: event.fields.id, type: "lemon", url: "https://battlemon.com/scripted.png" }"#;
+    // The node sends the package in lowercase.
+    let event_type = format!("{}::lemon::LemonCreated", PACKAGE.to_uppercase());
+    let app = spawn_indexer_with_script_for(1, event_type, script).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+
+    app.wait_for_nft(lemon, |_| true).await;
+    let resp = app
+        .graphql(&format!(r#"{{ nft(id: "{lemon}") {{ url }} }}"#))
+        .await;
+    assert_eq!(
+        resp["data"]["nft"]["url"], "https://battlemon.com/scripted.png",
+        "{resp}"
+    );
+}
+
+#[tokio::test]
+async fn scripted_and_parsed_nfts_are_created_at_event_time() {
+    let script = r#"#{ op: "upsert", id: event.fields.id, type: "lemon", url: "https://battlemon.com/scripted.png" }"#;
+    let app = spawn_indexer_with_script(1, "lemon::LemonCreated", script).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let timestamp = 1_600_000_000_000;
+
+    let mut envelope = app.lemon_created(lemon, owner);
+    envelope.timestamp = timestamp;
+    app.node().emit(&envelope);
+    let mut envelope = app.item_created(item, owner);
+    envelope.timestamp = timestamp;
+    app.node().emit(&envelope);
+    app.wait_for_nft(item, |_| true).await;
+
+    for (id, url) in [
+        (lemon, "https://battlemon.com/scripted.png".to_string()),
+        (item, format!("https://battlemon.com/nfts/{item}.png")),
+    ] {
+        let query = format!(r#"{{ nft(id: "{id}") {{ owner url createdAt }} }}"#);
+        let resp = app.graphql(&query).await;
+        let nft = &resp["data"]["nft"];
+        assert_eq!(nft["owner"], owner.to_string(), "{resp}");
+        assert_eq!(nft["url"], url);
+        let created_at = nft["createdAt"].as_str().unwrap();
+        let created_at = chrono::DateTime::parse_from_rfc3339(created_at).unwrap();
+        assert_eq!(created_at.timestamp_millis(), timestamp as i64);
+    }
+}
+
+#[tokio::test]
+async fn item_added_event_attaches_item_to_lemon() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.node().emit(&app.item_created(item, owner));
+    app.wait_for_nft(item, |_| true).await;
+    app.node().emit(&app.item_added(lemon, item));
+
+    let lemon = lemon.to_string();
+    let nft = app
+        .wait_for_nft(item, |nft| nft.attached_to.is_some())
+        .await;
+    assert_eq!(nft.attached_to, Some(lemon));
+}
+
+#[tokio::test]
+async fn attached_item_is_owned_by_lemon_and_its_owner() {
+    let app = spawn_indexer(1).await;
+    let (lemon_owner, item_owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let (lemon, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, lemon_owner));
+    app.node().emit(&app.item_created(item, item_owner));
+    app.wait_for_nft(item, |_| true).await;
+    app.node().emit(&app.item_added(lemon, item));
+    let nft = app
+        .wait_for_nft(item, |nft| nft.attached_to.is_some())
+        .await;
+    assert_eq!(nft.owner, lemon_owner.to_string());
+
+    let query =
+        format!(r#"{{ nft(id: "{item}") {{ owner ownership {{ kind object address }} }} }}"#);
+    let resp = app.graphql(&query).await;
+    let nft = &resp["data"]["nft"];
+    assert_eq!(nft["owner"], lemon_owner.to_string());
+    assert_eq!(nft["ownership"]["kind"], "OBJECT_OWNER");
+    assert_eq!(nft["ownership"]["object"], lemon.to_string());
+    assert!(nft["ownership"]["address"].is_null(), "{resp}");
+
+    let query = format!(
+        r#"{{ direct: nfts(owner: "{lemon_owner}") {{ nodes {{ id }} }} all: nfts(owner: "{lemon_owner}", indirect: true) {{ nodes {{ id }} }} }}"#
+    );
+    let resp = app.graphql(&query).await;
+    let ids = |key: &str| -> Vec<String> {
+        resp["data"][key]["nodes"]
+            .as_array()
+            .unwrap_or_else(|| panic!("{resp}"))
+            .iter()
+            .map(|nft| nft["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(ids("direct"), vec![lemon.to_string()]);
+    let mut all = ids("all");
+    all.sort();
+    let mut expected = vec![lemon.to_string(), item.to_string()];
+    expected.sort();
+    assert_eq!(all, expected);
+}
+
+#[tokio::test]
+async fn attached_items_take_their_slot_in_lemon_loadout() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, sword, axe, other) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.node()
+        .emit(&app.item_created_in_slot(sword, owner, "weapon"));
+    app.node()
+        .emit(&app.item_created_in_slot(axe, owner, "weapon"));
+    app.wait_for_nft(axe, |_| true).await;
+    app.node().emit(&app.item_added(lemon, sword));
+    app.wait_for_nft(sword, |nft| nft.attached_to.is_some())
+        .await;
+    // The weapon slot is taken, so the axe is rejected.
+    app.node().emit(&app.item_added(lemon, axe));
+    app.node().emit(&app.lemon_created(other, owner));
+    app.wait_for_nft(other, |_| true).await;
+
+    let query = format!(
+        r#"{{ nft(id: "{lemon}") {{ items {{ id }} loadout {{ head {{ id }} weapon {{ id slot }} }} }} }}"#
+    );
+    let resp = app.graphql(&query).await;
+    let nft = &resp["data"]["nft"];
+    assert_eq!(nft["items"].as_array().unwrap().len(), 1, "{resp}");
+    assert_eq!(nft["loadout"]["weapon"]["id"], sword.to_string());
+    assert_eq!(nft["loadout"]["weapon"]["slot"], "WEAPON");
+    assert!(nft["loadout"]["head"].is_null(), "{resp}");
+    let axe = app.wait_for_nft(axe, |_| true).await;
+    assert_eq!(axe.attached_to, None);
+}
+
+#[tokio::test]
+async fn indexer_resubscribes_after_connection_is_dropped() {
+    let app = spawn_indexer(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().drop_connections();
+    assert!(
+        app.node()
+            .wait_for_subscribers(1, Duration::from_secs(10))
+            .await
+    );
+    app.node().emit(&app.lemon_created(lemon, owner));
+
+    app.wait_for_nft(lemon, |_| true).await;
+}
+
+#[tokio::test]
+async fn indexer_fails_over_when_subscription_is_rejected() {
+    let app = spawn_indexer(2).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    let (active, standby) = match app.nodes[0].subscribers() {
+        0 => (&app.nodes[1], &app.nodes[0]),
+        _ => (&app.nodes[0], &app.nodes[1]),
+    };
+    active.fail_next("sui_subscribeEvent", -32000, "Node is overloaded");
+    active.drop_connections();
+
+    assert!(
+        standby
+            .wait_for_subscribers(1, Duration::from_secs(10))
+            .await
+    );
+    standby.emit(&app.lemon_created(lemon, owner));
+
+    app.wait_for_nft(lemon, |_| true).await;
+}
+
+#[tokio::test]
+async fn events_are_retried_while_the_verifier_is_unavailable() {
+    let app = spawn_indexer_verifying_events(2).await;
+    let (lemon, forged, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    let (active, verifier) = match app.nodes[0].subscribers() {
+        0 => (&app.nodes[1], &app.nodes[0]),
+        _ => (&app.nodes[0], &app.nodes[1]),
+    };
+    let event = app.lemon_created(lemon, owner);
+    verifier.add_transaction(
+        &event.tx_digest,
+        transaction_response("create_lemon", &[event.clone()]),
+    );
+    // The verifier flaps, the event is kept until it answers.
+    for _ in 0..2 {
+        verifier.fail_next("sui_getTransaction", -32000, "Node is overloaded");
+    }
+    active.emit(&event);
+    app.wait_for_nft(lemon, |_| true).await;
+
+    // The verifier knows the transaction, but not the event in it.
+    let event = app.lemon_created(forged, owner);
+    verifier.add_transaction(&event.tx_digest, transaction_response("create_lemon", &[]));
+    active.emit(&event);
+    let status = app
+        .wait_for_status(|status| status["failed_events"] == 1)
+        .await;
+    assert_eq!(status["processed_events"], 2);
+}
+
+#[tokio::test]
+async fn events_emitted_during_failover_are_backfilled() {
+    let app = spawn_indexer(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (seen, missed) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    app.node().emit(&app.lemon_created(seen, owner));
+    app.wait_for_nft(seen, |_| true).await;
+
+    // The first resubscription fails, so the event is emitted while nobody listens.
+    app.node()
+        .fail_next("sui_subscribeEvent", -32000, "Node is overloaded");
+    app.node().drop_connections();
+    app.node().emit(&app.lemon_created(missed, owner));
+
+    app.wait_for_nft(missed, |_| true).await;
+}
+
+#[tokio::test]
+async fn events_dropped_by_full_buffer_are_backfilled() {
+    // Room for a couple of events, the rest is dropped until the backend is up.
+    let app = spawn_indexer_with_small_buffer(1, 2048).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let lemons: Vec<_> = (0..5)
+        .map(|_| SuiAddress::random_for_testing_only())
+        .collect();
+
+    for &lemon in &lemons {
+        app.node().emit(&app.lemon_created(lemon, owner));
+    }
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    app.start_backend().await;
+
+    for &lemon in &lemons {
+        app.wait_for_nft(lemon, |_| true).await;
+    }
+}
+
+#[tokio::test]
+async fn standby_replica_takes_over_from_the_shared_cursor() {
+    let app = spawn_indexer_with_ha(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (before, after) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let replica = app.spawn_replica().await;
+
+    app.node().emit(&app.lemon_created(before, owner));
+    app.wait_for_nft(before, |_| true).await;
+    // The standby waits for the leader lock instead of subscribing.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(app.node().subscribers(), 1);
+
+    app.kill();
+    app.node().emit(&app.lemon_created(after, owner));
+
+    app.wait_for_nft(after, |_| true).await;
+    assert!(!replica.is_finished());
+}
+
+#[tokio::test]
+async fn buffered_events_are_delivered_once_backend_is_up() {
+    let app = spawn_indexer_with_backend_down(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.node().emit(&app.item_created(item, owner));
+    app.node().emit(&app.item_added(lemon, item));
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    app.start_backend().await;
+
+    let nft = app
+        .wait_for_nft(item, |nft| nft.attached_to.is_some())
+        .await;
+    assert_eq!(nft.attached_to, Some(lemon.to_string()));
+}
+
+#[tokio::test]
+async fn events_received_while_paused_are_applied_after_resume() {
+    let app = spawn_indexer_with_admin(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    let status: Value = app
+        .admin_post("/pause", json!({}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["paused"], true);
+    app.node().emit(&app.lemon_created(lemon, owner));
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let resp = app
+        .graphql(&format!(r#"{{ nft(id: "{lemon}") {{ id }} }}"#))
+        .await;
+    assert!(resp["data"]["nft"].is_null(), "{resp}");
+
+    let status: Value = app
+        .admin_post("/resume", json!({}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(status["paused"], false);
+    app.wait_for_nft(lemon, |_| true).await;
+}
+
+#[tokio::test]
+async fn rewind_to_cursor_reapplies_events_once() {
+    let app = spawn_indexer_with_admin(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, item, lost) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.node().emit(&app.item_created(item, owner));
+    app.node().emit(&app.item_added(lemon, item));
+    app.node().emit(&app.lemon_created(lost, owner));
+    app.wait_for_nft(lost, |_| true).await;
+    app.wait_for_nft(item, |nft| nft.attached_to.is_some())
+        .await;
+    sqlx::query("DELETE FROM nfts WHERE id = $1")
+        .bind(lost.to_string())
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    let cursor = serde_json::to_value(EventID {
+        tx_seq: 0,
+        event_seq: 0,
+    })
+    .unwrap();
+    let resp = app.admin_post("/rewind", json!({ "cursor": cursor })).await;
+    assert_eq!(resp.status().as_u16(), 202);
+
+    app.wait_for_nft(lost, |_| true).await;
+    let resp = app
+        .graphql(&format!(r#"{{ nft(id: "{lemon}") {{ items {{ id }} }} }}"#))
+        .await;
+    let items = resp["data"]["nft"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{resp}");
+}
+
+#[tokio::test]
+async fn dry_run_sends_no_mutations_to_backend() {
+    let app = spawn_indexer_in_dry_run(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (lemon, item) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, owner));
+    app.node().emit(&app.item_created(item, owner));
+    app.node().emit(&app.item_added(lemon, item));
+    app.wait_for_status(|status| status["processed_events"] == 3)
+        .await;
+
+    let resp = app.graphql("{ nfts { totalCount } }").await;
+    assert_eq!(resp["data"]["nfts"]["totalCount"], 0, "{resp}");
+    let resp = app.graphql("{ transactions { digest } }").await;
+    assert_eq!(resp["data"]["transactions"], json!([]), "{resp}");
+}
+
+#[tokio::test]
+async fn checkpoints_are_indexed_and_resumed_after_restart() {
+    let app = spawn_indexer_walking_checkpoints(1).await;
+    let owner = SuiAddress::random_for_testing_only();
+    let (first, second) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    let event = app.lemon_created(first, owner);
+    app.node().emit(&event);
+    let checkpoint = app.node().add_checkpoint(&[event.tx_digest]);
+    app.wait_for_nft(first, |_| true).await;
+    app.wait_for_status(|status| status["checkpoint"] == checkpoint)
+        .await;
+
+    // A restarted indexer must not walk the indexed checkpoint again.
+    app.kill();
+    sqlx::query("DELETE FROM nfts WHERE id = $1")
+        .bind(first.to_string())
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+    let event = app.lemon_created(second, owner);
+    app.node().emit(&event);
+    app.node().add_checkpoint(&[event.tx_digest]);
+    app.spawn_replica().await;
+
+    app.wait_for_nft(second, |_| true).await;
+    let row = sqlx::query("SELECT id FROM nfts WHERE id = $1")
+        .bind(first.to_string())
+        .fetch_optional(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(row.is_none(), "Checkpoint {checkpoint} was indexed again");
+}
+
+#[tokio::test]
+async fn event_transactions_are_recorded() {
+    let app = spawn_indexer_with_admin(1).await;
+    let (lemon, owner, other) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let event = app.lemon_created(lemon, owner);
+    app.node().add_transaction(
+        &event.tx_digest,
+        transaction_response("create_lemon", &[event.clone()]),
+    );
+
+    app.node().emit(&event);
+    // The node doesn't know this transaction, its event is applied anyway.
+    app.node().emit(&app.lemon_created(other, owner));
+    let status = app
+        .wait_for_status(|status| status["processed_events"] == 2)
+        .await;
+    assert_eq!(status["failed_events"], 0);
+    assert_eq!(status["unrecorded_transactions"], 1);
+    app.wait_for_nft(other, |_| true).await;
+
+    let resp = app
+        .graphql(&format!(
+            r#"{{ transactions(address: "{owner}") {{ digest sender status moveCalls {{ module function }} }} }}"#
+        ))
+        .await;
+    let transactions = resp["data"]["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 1, "{resp}");
+    assert_eq!(transactions[0]["digest"], event.tx_digest.to_string());
+    assert_eq!(transactions[0]["sender"], owner.to_string());
+    assert_eq!(transactions[0]["status"], "SUCCESS");
+    assert_eq!(
+        transactions[0]["moveCalls"],
+        json!([{ "module": "lemon", "function": "create_lemon" }])
+    );
+}
+
+#[tokio::test]
+async fn same_object_on_two_networks_is_kept_apart() {
+    let app = spawn_indexer(1).await;
+    let (devnet, _indexer) = app.spawn_on_network(Network::Devnet).await;
+    let (lemon, localnet_owner, devnet_owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+
+    app.node().emit(&app.lemon_created(lemon, localnet_owner));
+    devnet.emit(&app.lemon_created(lemon, devnet_owner));
+
+    for (network, owner) in [("LOCALNET", localnet_owner), ("DEVNET", devnet_owner)] {
+        let query = format!(r#"{{ nft(id: "{lemon}", network: {network}) {{ owner }} }}"#);
+        app.wait_for_graphql(&query, |resp| {
+            resp["data"]["nft"]["owner"] == owner.to_string()
+        })
+        .await;
+        let resp = app
+            .graphql(&format!("{{ nfts(network: {network}) {{ totalCount }} }}"))
+            .await;
+        assert_eq!(resp["data"]["nfts"]["totalCount"], 1, "{resp}");
+
+        let query =
+            format!(r#"{{ nft(id: "{lemon}", network: {network}) {{ globalId network }} }}"#);
+        let resp = app.graphql(&query).await;
+        assert_eq!(resp["data"]["nft"]["network"], network, "{resp}");
+        let global_id = &resp["data"]["nft"]["globalId"];
+        let query =
+            format!(r#"{{ node(globalId: {global_id}) {{ ... on Nft {{ owner network }} }} }}"#);
+        let resp = app.graphql(&query).await;
+        assert_eq!(resp["data"]["node"]["owner"], owner.to_string(), "{resp}");
+        assert_eq!(resp["data"]["node"]["network"], network, "{resp}");
+    }
+}
+
+#[tokio::test]
+async fn numbers_beyond_int_range_are_kept() {
+    let app = spawn_indexer(1).await;
+    let (lemon, owner) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let traits = vec![
+        ("level", SuiMoveValue::Number(7)),
+        ("luck", SuiMoveValue::Number(i32::MAX as u64)),
+        ("stamina", SuiMoveValue::Number(1 << 31)),
+        ("power", SuiMoveValue::Number(u64::MAX)),
+    ];
+
+    app.node()
+        .emit(&app.lemon_created_with_traits(lemon, owner, traits));
+    app.wait_for_nft(lemon, |_| true).await;
+
+    let resp = app
+        .graphql(&format!(
+            r#"{{ nft(id: "{lemon}") {{ traits {{ name flavour {{ kind number bigNumber }} }} }} }}"#
+        ))
+        .await;
+    assert_eq!(
+        resp["data"]["nft"]["traits"],
+        json!([
+            { "name": "level", "flavour": { "kind": "NUMBER", "number": 7, "bigNumber": null } },
+            {
+                "name": "luck",
+                "flavour": { "kind": "NUMBER", "number": i32::MAX, "bigNumber": null },
+            },
+            {
+                "name": "stamina",
+                "flavour": { "kind": "NUMBER", "number": null, "bigNumber": "2147483648" },
+            },
+            {
+                "name": "power",
+                "flavour": { "kind": "NUMBER", "number": null, "bigNumber": u64::MAX.to_string() },
+            },
+        ]),
+        "{resp}"
+    );
+}
@@ -0,0 +1,104 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::ParseStream;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr,
+    Token,
+};
+
+/// Derive `models::move_struct::FromMoveStruct` for a struct with named fields.
+///
+/// Each field is taken from the Move field of the same name, `#[move_struct(rename = "name")]`
+/// takes it from another one. Fields are decoded with `FromMoveValue`, so `Option`, `Vec` and
+/// other structs deriving `FromMoveStruct` may be nested. Type parameters are required to
+/// implement `FromMoveValue` too.
+#[proc_macro_derive(FromMoveStruct, attributes(move_struct))]
+pub fn derive_from_move_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`FromMoveStruct` can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "`FromMoveStruct` can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut idents = Vec::new();
+    let mut move_names = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.clone().expect("named fields have identifiers");
+        let move_name = match rename(&field.attrs)? {
+            Some(name) => name,
+            None => ident.to_string().trim_start_matches("r#").to_string(),
+        };
+        idents.push(ident);
+        move_names.push(move_name);
+    }
+
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let predicates = &mut generics.make_where_clause().predicates;
+    for param in params {
+        predicates.push(parse_quote!(#param: ::models::move_struct::FromMoveValue));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::models::move_struct::FromMoveStruct for #name #ty_generics #where_clause {
+            const FIELDS: &'static [&'static str] = &[#(#move_names),*];
+
+            fn from_move_struct(
+                mut fields: ::models::move_struct::MoveFields,
+                path: &str,
+            ) -> ::std::result::Result<Self, ::models::errors::Error> {
+                Ok(Self {
+                    #(#idents: ::models::move_struct::field(&mut fields, path, #move_names)?,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::models::move_struct::FromMoveValue for #name #ty_generics #where_clause {
+            fn from_move_value(
+                value: ::models::sui_sdk::rpc_types::SuiMoveValue,
+                path: &str,
+            ) -> ::std::result::Result<Self, ::models::errors::Error> {
+                ::models::move_struct::struct_from_value(value, path)
+            }
+        }
+    })
+}
+
+/// Name given by `#[move_struct(rename = "name")]`, if any.
+fn rename(attrs: &[Attribute]) -> syn::Result<Option<String>> {
+    let mut ret = None;
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("move_struct"))
+    {
+        let name = attr.parse_args_with(|input: ParseStream| {
+            let key: Ident = input.parse()?;
+            if key != "rename" {
+                return Err(Error::new_spanned(&key, "expected `rename = \"...\"`"));
+            }
+            input.parse::<Token![=]>()?;
+            Ok(input.parse::<LitStr>()?.value())
+        })?;
+        ret = Some(name);
+    }
+    Ok(ret)
+}
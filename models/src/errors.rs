@@ -1,13 +1,81 @@
+use serde::Serialize;
+use std::fmt;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("The Sui's event with name `{0}` is unsupported")]
     UnsupportedSuiEvent(String),
     #[error("The event must have fields")]
     EventWithoutFields,
-    #[error("Failed to split event type on parts")]
-    EventTypeSplit,
-    #[error("The event type with name `{0}` is unsupported")]
-    UnsupportedEventType(String),
+    #[error("`{type_}` is not a valid Move type: {reason}")]
+    InvalidMoveType { type_: String, reason: String },
+    #[error("No handler is registered for the event type `{0}`")]
+    UnknownEventType(String),
+    #[cfg(feature = "sui-sdk")]
+    #[error("Failed to (de)serialize the event's BCS payload: {0}")]
+    Bcs(#[from] bcs::Error),
     #[error("The event's field with name `{0}` doesn't exist")]
     WrongEventFieldName(String),
+    #[error("The event's field `{path}` is {found}, expected {expected}")]
+    EventFieldType {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error(
+        "The event was rejected by strict parsing: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    StrictParsing(Vec<Diagnostic>),
+    #[error("`{0}` is not a known kind of NFT")]
+    UnknownNftKind(String),
+    #[error("`{0}` is not a known equipment slot")]
+    UnknownSlot(String),
+    #[error("`{0}` is not a known Sui network")]
+    UnknownNetwork(String),
+    #[error("`{0}` is not a valid global id")]
+    InvalidGlobalId(String),
+    #[error("`{value}` is not a valid Sui {kind}: {reason}")]
+    InvalidSuiId {
+        kind: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+}
+
+/// Problem found in an event's fields, `path` points at the field, e.g. `traits[2].flavour`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Diagnostic {
+    /// The field couldn't be parsed and was left out.
+    SkippedField { path: String, reason: String },
+    /// The field has another type than expected and was left out.
+    TypeMismatch {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The field isn't known to the parser and was ignored.
+    UnknownField { path: String },
+    /// Decoding the BCS payload failed or gave another event than the fields.
+    DecoderMismatch { reason: String },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::SkippedField { path, reason } => {
+                write!(f, "skipped field `{path}`: {reason}")
+            }
+            Diagnostic::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => write!(f, "field `{path}` is {found}, expected {expected}"),
+            Diagnostic::UnknownField { path } => write!(f, "unknown field `{path}`"),
+            Diagnostic::DecoderMismatch { reason } => {
+                write!(f, "BCS payload disagrees with the fields: {reason}")
+            }
+        }
+    }
 }
@@ -1,118 +1,839 @@
+pub use crate::errors::Diagnostic;
 use crate::errors::Error;
-use crate::{Item, Nft, Trait};
-use chrono::Utc;
-use std::collections::BTreeMap;
-use sui_sdk::rpc_types::{SuiEvent, SuiMoveStruct, SuiMoveValue};
+use crate::move_events::{
+    ItemAttachment as MoveItemAttachment, MoveFlavour, NftCreated as MoveNftCreated,
+};
+use crate::move_struct::{field_path, value_kind, FromMoveStruct, MoveFields};
+use crate::move_type::{StructTag, TypeTag};
+use crate::{
+    Address, Item, KindSpec, Nft, NftKind, ObjectId, Ownership, Slot, Trait, TraitValue,
+    UnknownEvent,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use sui_sdk::rpc_types::{SuiEvent, SuiEventEnvelope, SuiMoveStruct, SuiMoveValue};
 use sui_sdk::types::base_types::SuiAddress;
 
-const LEMON_ID: &str = "lemon_id";
-const ITEM_ID: &str = "item_id";
-const ID: &str = "id";
-const URL: &str = "url";
 const TRAITS: &str = "traits";
 const NAME: &str = "name";
 const FLAVOUR: &str = "flavour";
+const SLOT: &str = "slot";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Event {
     Nft(Nft),
     ItemAdded(Item),
     ItemRemoved(Item),
+    /// Event of a type without a registered handler.
+    Unknown(UnknownEvent),
+}
+
+/// How to treat problems which don't prevent an event from being parsed.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseMode {
+    /// Parse what's possible and report the rest as diagnostics.
+    #[default]
+    Lenient,
+    /// Reject events with any diagnostics.
+    Strict,
+}
+
+/// Which rendering of Move events is decoded.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EventDecoder {
+    /// The JSON fields rendered by the node.
+    #[default]
+    Fields,
+    /// The raw BCS payload, which keeps the exact Move types.
+    Bcs,
+    /// Both, the fields are indexed and disagreements with the BCS payload are reported.
+    Compare,
+}
+
+/// Event alongside the diagnostics of its parsing.
+#[derive(Debug, Clone)]
+pub struct ParsedEvent {
+    pub event: Event,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Fields of a Move event, keyed by the field's name.
+pub type EventFields = MoveFields;
+
+/// Parses the fields of one event type, gets the event's type, sender and time alongside them.
+pub type EventHandler = Box<
+    dyn Fn(
+            &StructTag,
+            EventFields,
+            SuiAddress,
+            DateTime<Utc>,
+            &mut Vec<Diagnostic>,
+        ) -> Result<Event, Error>
+        + Send
+        + Sync,
+>;
+
+/// Decodes the BCS payload of one event type, gets the event's type, sender and time alongside it.
+pub type BcsHandler = Box<
+    dyn Fn(
+            &StructTag,
+            &[u8],
+            SuiAddress,
+            DateTime<Utc>,
+            &mut Vec<Diagnostic>,
+        ) -> Result<Event, Error>
+        + Send
+        + Sync,
+>;
+
+/// Routes Move events to the handler registered for their fully qualified type.
+///
+/// Handlers are keyed by package, module and struct name. Type parameters are passed to the
+/// handler, so one handler serves every instantiation of a generic event.
+#[derive(Default)]
+pub struct EventRegistry {
+    handlers: HashMap<(ObjectId, String, String), EventHandler>,
+    bcs_handlers: HashMap<(ObjectId, String, String), BcsHandler>,
+    decoder: EventDecoder,
+}
+
+impl fmt::Debug for EventRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let types: Vec<_> = self
+            .handlers
+            .keys()
+            .map(|(package, module, name)| format!("{package}::{module}::{name}"))
+            .collect();
+        f.debug_struct("EventRegistry")
+            .field("types", &types)
+            .field("decoder", &self.decoder)
+            .finish()
+    }
 }
 
-impl TryFrom<SuiEvent> for Event {
-    type Error = Error;
+impl EventRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry of the contract's events as emitted by any of `packages`, an upgraded
+    /// contract keeps emitting events typed with its original package.
+    pub fn contract(packages: impl IntoIterator<Item = ObjectId>) -> Self {
+        let mut registry = Self::new();
+        // Items are attached through the module of the NFT they're attached to.
+        let attach_module = NftKind::Lemon.spec().module;
+        for package in packages {
+            for kind in NftKind::ALL {
+                let KindSpec {
+                    module,
+                    created_event,
+                    ..
+                } = kind.spec();
+                registry
+                    .register(
+                        package.clone(),
+                        module,
+                        created_event,
+                        move |_, fields, sender, timestamp, diagnostics| {
+                            parse_event_nft_created(fields, sender, timestamp, kind, diagnostics)
+                        },
+                    )
+                    .register_bcs(
+                        package.clone(),
+                        module,
+                        created_event,
+                        move |event_type, bytes, sender, timestamp, diagnostics| {
+                            decode_nft_created(
+                                event_type,
+                                bytes,
+                                sender,
+                                timestamp,
+                                kind,
+                                diagnostics,
+                            )
+                        },
+                    );
+            }
+            registry
+                .register(
+                    package.clone(),
+                    attach_module,
+                    "ItemAdded",
+                    |_, fields, _, _, diagnostics| parse_event_item_added(fields, diagnostics),
+                )
+                .register(
+                    package.clone(),
+                    attach_module,
+                    "ItemRemoved",
+                    |_, fields, _, _, diagnostics| parse_event_item_removed(fields, diagnostics),
+                )
+                .register_bcs(
+                    package.clone(),
+                    attach_module,
+                    "ItemAdded",
+                    |_, bytes, _, _, diagnostics| {
+                        let item = decode_item_attachment(bytes, diagnostics)?;
+                        Ok(Event::ItemAdded(item))
+                    },
+                )
+                .register_bcs(
+                    package,
+                    attach_module,
+                    "ItemRemoved",
+                    |_, bytes, _, _, diagnostics| {
+                        let item = decode_item_attachment(bytes, diagnostics)?;
+                        Ok(Event::ItemRemoved(item))
+                    },
+                );
+        }
+        registry
+    }
+
+    pub fn with_decoder(mut self, decoder: EventDecoder) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// Register the handler of `package::module::name` events, replacing the previous one.
+    pub fn register<F>(
+        &mut self,
+        package: ObjectId,
+        module: &str,
+        name: &str,
+        handler: F,
+    ) -> &mut Self
+    where
+        F: Fn(
+                &StructTag,
+                EventFields,
+                SuiAddress,
+                DateTime<Utc>,
+                &mut Vec<Diagnostic>,
+            ) -> Result<Event, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let key = (package, module.to_string(), name.to_string());
+        self.handlers.insert(key, Box::new(handler));
+        self
+    }
 
-    fn try_from(event: SuiEvent) -> Result<Self, Self::Error> {
-        let SuiEvent::MoveEvent { sender, fields, type_: event_type, .. } = event else {
+    /// Register the BCS decoder of `package::module::name` events, replacing the previous one.
+    pub fn register_bcs<F>(
+        &mut self,
+        package: ObjectId,
+        module: &str,
+        name: &str,
+        handler: F,
+    ) -> &mut Self
+    where
+        F: Fn(
+                &StructTag,
+                &[u8],
+                SuiAddress,
+                DateTime<Utc>,
+                &mut Vec<Diagnostic>,
+            ) -> Result<Event, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let key = (package, module.to_string(), name.to_string());
+        self.bcs_handlers.insert(key, Box::new(handler));
+        self
+    }
+
+    /// Whether events of the fully qualified `event_type` are decoded by a handler.
+    pub fn handles(&self, event_type: &str) -> bool {
+        event_type.parse::<StructTag>().map_or(false, |event_type| {
+            self.is_known(&(event_type.package, event_type.module, event_type.name))
+        })
+    }
+
+    fn is_known(&self, key: &(ObjectId, String, String)) -> bool {
+        match self.decoder {
+            EventDecoder::Bcs => self.bcs_handlers.contains_key(key),
+            EventDecoder::Fields | EventDecoder::Compare => self.handlers.contains_key(key),
+        }
+    }
+
+    /// Parse the envelope's event, events of types without a handler are parsed as
+    /// [`Event::Unknown`].
+    pub fn parse(
+        &self,
+        envelope: &SuiEventEnvelope,
+        mode: ParseMode,
+    ) -> Result<ParsedEvent, Error> {
+        let timestamp = event_time(envelope);
+        let event = envelope.event.clone();
+        let SuiEvent::MoveEvent { sender, fields, type_: event_type, bcs, .. } = event else {
             return Err(Error::UnsupportedSuiEvent(event.get_event_type()));
         };
 
-        let Some(SuiMoveStruct::WithFields(fields)) = fields else {
-            return Err(Error::EventWithoutFields);
+        let event_type = event_type.parse::<StructTag>()?;
+        let key = (
+            event_type.package.clone(),
+            event_type.module.clone(),
+            event_type.name.clone(),
+        );
+        if !self.is_known(&key) {
+            let event = UnknownEvent {
+                r#type: event_type.to_string(),
+                sender: sender.into(),
+                fields: serde_json::to_value(&fields).unwrap_or_default(),
+            };
+            return Ok(ParsedEvent {
+                event: Event::Unknown(event),
+                diagnostics: Vec::new(),
+            });
+        }
+
+        let decode_bcs = |diagnostics: &mut Vec<Diagnostic>| match self.bcs_handlers.get(&key) {
+            Some(handler) => handler(&event_type, &bcs, sender, timestamp, diagnostics),
+            None => Err(Error::UnknownEventType(event_type.to_string())),
+        };
+        let mut diagnostics = Vec::new();
+        let event = if self.decoder == EventDecoder::Bcs {
+            decode_bcs(&mut diagnostics)?
+        } else {
+            let Some(handler) = self.handlers.get(&key) else {
+                return Err(Error::UnknownEventType(event_type.to_string()));
+            };
+
+            let Some(SuiMoveStruct::WithFields(fields)) = fields else {
+                return Err(Error::EventWithoutFields);
+            };
+
+            let event = handler(&event_type, fields, sender, timestamp, &mut diagnostics)?;
+
+            // Problems of the payload are the fields' problems too, they're reported once.
+            if self.decoder == EventDecoder::Compare {
+                if let Some(reason) = compare_decoders(&event, decode_bcs(&mut Vec::new())) {
+                    diagnostics.push(Diagnostic::DecoderMismatch { reason });
+                }
+            }
+            event
         };
 
-        match event_type.rsplit("::").next() {
-            Some("LemonCreated") => parse_event_nft_created(fields, sender, "lemon"),
-            Some("ItemCreated") => parse_event_nft_created(fields, sender, "item"),
-            Some("ItemAdded") => parse_event_item_added(fields),
-            Some("ItemRemoved") => parse_event_item_removed(fields),
-            None => Err(Error::EventTypeSplit),
-            Some(rest) => Err(Error::UnsupportedEventType(rest.to_string())),
+        if mode == ParseMode::Strict && !diagnostics.is_empty() {
+            return Err(Error::StrictParsing(diagnostics));
         }
+
+        Ok(ParsedEvent { event, diagnostics })
     }
 }
 
-fn parse_event_item_added(mut fields: BTreeMap<String, SuiMoveValue>) -> Result<Event, Error> {
-    let Some(SuiMoveValue::Address(lemon_id)) = fields.remove(LEMON_ID) else {
-        return Err(Error::WrongEventFieldName(LEMON_ID.to_string()))
-    };
+/// Time the node stamped the envelope with, in milliseconds since the epoch.
+pub fn event_time(envelope: &SuiEventEnvelope) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(envelope.timestamp as i64)
+        .single()
+        .unwrap_or_else(Utc::now)
+}
 
-    let Some(SuiMoveValue::Address(item_id)) = fields.remove(ITEM_ID) else {
-        return Err(Error::WrongEventFieldName(ITEM_ID.to_string()))
+/// Why the BCS decoding disagrees with the event decoded from the fields, if it does.
+fn compare_decoders(event: &Event, bcs_event: Result<Event, Error>) -> Option<String> {
+    let bcs_event = match bcs_event {
+        Ok(bcs_event) => bcs_event,
+        Err(err) => return Some(err.to_string()),
     };
 
-    Ok(Event::ItemAdded(Item {
-        lemon_id: lemon_id.to_string(),
-        item_id: item_id.to_string(),
-    }))
+    (event != &bcs_event).then(|| format!("decoded as {bcs_event:?}"))
 }
 
-fn parse_event_item_removed(mut fields: BTreeMap<String, SuiMoveValue>) -> Result<Event, Error> {
-    let Some(SuiMoveValue::Address(lemon_id)) = fields.remove(LEMON_ID) else {
-        return Err(Error::WrongEventFieldName(LEMON_ID.to_string()))
-    };
+/// Decode the payload of an NFT created event, flavours have the event's type parameter
+/// type, or are strings when it has none.
+fn decode_nft_created(
+    event_type: &StructTag,
+    bytes: &[u8],
+    sender: SuiAddress,
+    timestamp: DateTime<Utc>,
+    kind: NftKind,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Event, Error> {
+    fn decode<F: MoveFlavour>(
+        bytes: &[u8],
+        sender: SuiAddress,
+        timestamp: DateTime<Utc>,
+        kind: NftKind,
+    ) -> Result<Nft, Error> {
+        let event: MoveNftCreated<F> = bcs::from_bytes(bytes)?;
+        Ok(event.into_nft(kind, sender, timestamp))
+    }
 
-    let Some(SuiMoveValue::Address(item_id)) = fields.remove(ITEM_ID) else {
-        return Err(Error::WrongEventFieldName(ITEM_ID.to_string()))
+    let mut nft = match event_type.type_params.as_slice() {
+        [] => decode::<String>(bytes, sender, timestamp, kind)?,
+        [TypeTag::Struct(tag)] if is_move_string(tag) => {
+            decode::<String>(bytes, sender, timestamp, kind)?
+        }
+        [TypeTag::Bool] => decode::<bool>(bytes, sender, timestamp, kind)?,
+        [TypeTag::U8] => decode::<u8>(bytes, sender, timestamp, kind)?,
+        [TypeTag::U16] => decode::<u16>(bytes, sender, timestamp, kind)?,
+        [TypeTag::U32] => decode::<u32>(bytes, sender, timestamp, kind)?,
+        [TypeTag::U64] => decode::<u64>(bytes, sender, timestamp, kind)?,
+        [TypeTag::Address] => decode::<SuiAddress>(bytes, sender, timestamp, kind)?,
+        _ => {
+            return Err(Error::InvalidMoveType {
+                type_: event_type.to_string(),
+                reason: "trait flavours of this type can't be decoded".to_string(),
+            })
+        }
     };
+    nft.traits = std::mem::take(&mut nft.traits)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            check_trait_kind(item, kind, &format!("{TRAITS}[{idx}]"), diagnostics)
+        })
+        .collect();
 
-    Ok(Event::ItemRemoved(Item {
-        lemon_id: lemon_id.to_string(),
-        item_id: item_id.to_string(),
-    }))
+    Ok(Event::Nft(nft))
+}
+
+/// Whether the tag is `std::string::String` or `std::ascii::String`.
+fn is_move_string(tag: &StructTag) -> bool {
+    let std_package = "0x1".parse::<ObjectId>().expect("valid address");
+    tag.package == std_package
+        && (tag.module == "string" || tag.module == "ascii")
+        && tag.name == "String"
+        && tag.type_params.is_empty()
+}
+
+/// Fields of `LemonCreated` and `ItemCreated` events.
+#[derive(FromMoveStruct)]
+struct NftCreated {
+    id: ObjectId,
+    url: String,
+    traits: Vec<SuiMoveValue>,
+}
+
+/// Fields of `ItemAdded` and `ItemRemoved` events.
+#[derive(FromMoveStruct)]
+struct ItemAttachment {
+    lemon_id: ObjectId,
+    item_id: ObjectId,
+    slot: Option<String>,
+}
+
+/// Decode the event's fields, fields which aren't part of `T` are reported.
+fn decode<T: FromMoveStruct>(
+    fields: EventFields,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<T, Error> {
+    report_unknown_fields(
+        fields
+            .keys()
+            .filter(|name| !T::FIELDS.contains(&name.as_str())),
+        "",
+        diagnostics,
+    );
+    T::from_move_struct(fields, "")
+}
+
+fn parse_event_item_added(
+    fields: EventFields,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Event, Error> {
+    let item = parse_item_attachment(fields, diagnostics)?;
+    Ok(Event::ItemAdded(item))
+}
+
+fn parse_event_item_removed(
+    fields: EventFields,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Event, Error> {
+    let item = parse_item_attachment(fields, diagnostics)?;
+    Ok(Event::ItemRemoved(item))
+}
+
+fn parse_item_attachment(
+    fields: EventFields,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Item, Error> {
+    let ItemAttachment {
+        lemon_id,
+        item_id,
+        slot,
+    } = decode(fields, diagnostics)?;
+
+    Ok(Item {
+        lemon_id,
+        item_id,
+        slot: parse_slot(slot, diagnostics),
+    })
+}
+
+/// Decode the payload of an item attachment event, like `parse_item_attachment`.
+fn decode_item_attachment(bytes: &[u8], diagnostics: &mut Vec<Diagnostic>) -> Result<Item, Error> {
+    let MoveItemAttachment {
+        lemon_id,
+        item_id,
+        slot,
+    } = bcs::from_bytes(bytes)?;
+
+    Ok(Item {
+        lemon_id: lemon_id.into(),
+        item_id: item_id.into(),
+        slot: parse_slot(slot, diagnostics),
+    })
+}
+
+/// An unknown slot is reported and left out, the backend derives it from the item then.
+fn parse_slot(slot: Option<String>, diagnostics: &mut Vec<Diagnostic>) -> Option<Slot> {
+    slot.and_then(|slot| match slot.parse::<Slot>() {
+        Ok(slot) => Some(slot),
+        Err(err) => {
+            diagnostics.push(Diagnostic::SkippedField {
+                path: SLOT.to_string(),
+                reason: err.to_string(),
+            });
+            None
+        }
+    })
 }
 
 fn parse_event_nft_created(
-    fields: BTreeMap<String, SuiMoveValue>,
+    fields: EventFields,
     sender: SuiAddress,
-    nft_type: &str,
+    timestamp: DateTime<Utc>,
+    kind: NftKind,
+    diagnostics: &mut Vec<Diagnostic>,
 ) -> Result<Event, Error> {
-    let Some(SuiMoveValue::Address(id)) = fields.get(ID) else {
-        return Err(Error::WrongEventFieldName(ID.to_string()))
+    let NftCreated { id, url, traits } = decode(fields, diagnostics)?;
+
+    let ret_traits = traits
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| {
+            let path = format!("{TRAITS}[{idx}]");
+            let item = parse_trait(item, &path, diagnostics)?;
+            check_trait_kind(item, kind, &path, diagnostics)
+        })
+        .collect();
+
+    let owner: Address = sender.into();
+    Ok(Event::Nft(Nft {
+        id,
+        network: None,
+        r#type: kind,
+        ownership: Some(Ownership::AddressOwner {
+            address: owner.clone(),
+        }),
+        owner,
+        url,
+        traits: ret_traits,
+        items: Vec::new(),
+        created_at: timestamp,
+        attached_to: None,
+        slot: None,
+    }))
+}
+
+/// Keep the trait if its value has the type the kind declares for it, if any.
+fn check_trait_kind(
+    item: Trait,
+    kind: NftKind,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Trait> {
+    match kind.trait_kind(&item.name) {
+        Some(expected) if expected != item.flavour.value_kind() => {
+            diagnostics.push(Diagnostic::TypeMismatch {
+                path: format!("{path}.{FLAVOUR}"),
+                expected: expected.as_str(),
+                found: item.flavour.value_kind().as_str(),
+            });
+            None
+        }
+        _ => Some(item),
+    }
+}
+
+/// Parse a `{ name, flavour }` trait struct, a malformed trait is reported and left out.
+fn parse_trait(
+    item: &SuiMoveValue,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Trait> {
+    let fields = match item {
+        SuiMoveValue::Struct(SuiMoveStruct::WithTypes { fields, .. })
+        | SuiMoveValue::Struct(SuiMoveStruct::WithFields(fields)) => fields,
+        other => {
+            diagnostics.push(Diagnostic::TypeMismatch {
+                path: path.to_string(),
+                expected: "struct",
+                found: value_kind(other),
+            });
+            return None;
+        }
     };
-    let Some(SuiMoveValue::String(url)) = fields.get(URL) else {
-        return Err(Error::WrongEventFieldName(URL.to_string()))
+
+    let known = [NAME, FLAVOUR];
+    report_unknown_fields(
+        fields.keys().filter(|name| !known.contains(&name.as_str())),
+        path,
+        diagnostics,
+    );
+
+    let name = match fields.get(NAME) {
+        Some(SuiMoveValue::String(name)) => name,
+        Some(other) => {
+            diagnostics.push(Diagnostic::TypeMismatch {
+                path: format!("{path}.{NAME}"),
+                expected: "string",
+                found: value_kind(other),
+            });
+            return None;
+        }
+        None => {
+            diagnostics.push(Diagnostic::SkippedField {
+                path: path.to_string(),
+                reason: format!("no `{NAME}` field"),
+            });
+            return None;
+        }
     };
-    let Some(SuiMoveValue::Vector(traits)) = fields.get(TRAITS) else {
-        return Err(Error::WrongEventFieldName(TRAITS.to_string()))
+
+    let Some(flavour) = fields.get(FLAVOUR) else {
+        diagnostics.push(Diagnostic::SkippedField {
+            path: path.to_string(),
+            reason: format!("no `{FLAVOUR}` field"),
+        });
+        return None;
     };
 
-    let mut ret_traits = Vec::new();
-    for item in traits {
-        let SuiMoveValue::Struct(SuiMoveStruct::WithTypes { fields, .. }) = item else {
-            continue
-        };
+    let flavour = parse_trait_value(flavour, &format!("{path}.{FLAVOUR}"), diagnostics)?;
+    Some(Trait {
+        name: name.clone(),
+        flavour,
+    })
+}
 
-        let Some(SuiMoveValue::String(name)) = fields.get(NAME).cloned() else {
-            continue
-        };
+/// Convert any Move value into a trait value, values which can't be converted are reported.
+fn parse_trait_value(
+    value: &SuiMoveValue,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<TraitValue> {
+    let value = match value {
+        SuiMoveValue::String(value) => TraitValue::String(value.clone()),
+        SuiMoveValue::Number(value) => match i32::try_from(*value) {
+            Ok(value) => TraitValue::Number(value.into()),
+            Err(_) => TraitValue::BigNumber(value.to_string()),
+        },
+        SuiMoveValue::Bool(value) => TraitValue::Bool(*value),
+        SuiMoveValue::Address(address) => TraitValue::String(address.to_string()),
+        SuiMoveValue::UID { id } => TraitValue::String(id.to_string()),
+        SuiMoveValue::Option(value) => match value.as_ref() {
+            Some(value) => return parse_trait_value(value, path, diagnostics),
+            None => {
+                diagnostics.push(Diagnostic::SkippedField {
+                    path: path.to_string(),
+                    reason: "empty option".to_string(),
+                });
+                return None;
+            }
+        },
+        SuiMoveValue::Vector(values) | SuiMoveValue::Struct(SuiMoveStruct::Runtime(values)) => {
+            let values = values
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, value)| {
+                    parse_trait_value(value, &format!("{path}[{idx}]"), diagnostics)
+                })
+                .collect();
+            TraitValue::List(values)
+        }
+        SuiMoveValue::Struct(
+            SuiMoveStruct::WithTypes { fields, .. } | SuiMoveStruct::WithFields(fields),
+        ) => {
+            let traits = fields
+                .iter()
+                .filter_map(|(name, value)| {
+                    let flavour = parse_trait_value(value, &format!("{path}.{name}"), diagnostics)?;
+                    Some(Trait {
+                        name: name.clone(),
+                        flavour,
+                    })
+                })
+                .collect();
+            TraitValue::Nested(traits)
+        }
+    };
 
-        let Some(SuiMoveValue::String(flavour)) = fields.get(FLAVOUR).cloned() else {
-            continue
-        };
+    Some(value)
+}
 
-        ret_traits.push(Trait { name, flavour })
+fn report_unknown_fields<'a>(
+    names: impl Iterator<Item = &'a String>,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for name in names {
+        let path = field_path(path, name);
+        diagnostics.push(Diagnostic::UnknownField { path });
     }
+}
 
-    Ok(Event::Nft(Nft {
-        id: id.to_string(),
-        r#type: nft_type.to_string(),
-        owner: sender.to_string(),
-        url: url.to_owned(),
-        traits: ret_traits,
-        items: Vec::new(),
-        created_at: Utc::now(),
-        attached_to: None,
-    }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_events::{encode_bcs, MoveTrait};
+    use sui_sdk::rpc_types::EventID;
+    use sui_sdk::types::base_types::{ObjectID, TransactionDigest};
+    use sui_sdk::types::Identifier;
+
+    const PACKAGE: &str = "0x9b5b3f8e3fd1c6aaf2e8f5a4e1f5bd2c1d2e7f40";
+
+    fn registry(decoder: EventDecoder) -> EventRegistry {
+        EventRegistry::contract([PACKAGE.parse().unwrap()]).with_decoder(decoder)
+    }
+
+    /// `LemonCreated<u64>` with the traits in both its fields and its payload.
+    fn numeric_lemon_created(traits: &[(&str, u64)]) -> SuiEventEnvelope {
+        let id = SuiAddress::random_for_testing_only();
+        let url = "https://battlemon.com/lemon.png".to_string();
+        let bcs = bcs::to_bytes(&MoveNftCreated {
+            id,
+            url: url.clone(),
+            traits: traits
+                .iter()
+                .map(|&(name, flavour)| MoveTrait {
+                    name: name.to_string(),
+                    flavour,
+                })
+                .collect(),
+        })
+        .unwrap();
+        let traits = traits
+            .iter()
+            .map(|&(name, flavour)| {
+                SuiMoveValue::Struct(SuiMoveStruct::WithFields(MoveFields::from([
+                    (NAME.to_string(), SuiMoveValue::String(name.to_string())),
+                    (FLAVOUR.to_string(), SuiMoveValue::Number(flavour)),
+                ])))
+            })
+            .collect();
+        let fields = MoveFields::from([
+            ("id".to_string(), SuiMoveValue::Address(id)),
+            ("url".to_string(), SuiMoveValue::String(url)),
+            (TRAITS.to_string(), SuiMoveValue::Vector(traits)),
+        ]);
+
+        envelope("LemonCreated<u64>", fields, bcs)
+    }
+
+    /// `ItemAdded` with the slot, if any, in both its fields and its payload.
+    fn item_added(slot: Option<&str>) -> SuiEventEnvelope {
+        let mut fields = MoveFields::from([
+            (
+                "lemon_id".to_string(),
+                SuiMoveValue::Address(SuiAddress::random_for_testing_only()),
+            ),
+            (
+                "item_id".to_string(),
+                SuiMoveValue::Address(SuiAddress::random_for_testing_only()),
+            ),
+        ]);
+        let slot = slot.map(|slot| SuiMoveValue::String(slot.to_string()));
+        fields.insert(SLOT.to_string(), SuiMoveValue::Option(Box::new(slot)));
+        let bcs = encode_bcs("ItemAdded", fields.clone()).unwrap();
+
+        envelope("ItemAdded", fields, bcs)
+    }
+
+    /// Event of the `lemon` module, `name` may have type parameters.
+    fn envelope(name: &str, fields: MoveFields, bcs: Vec<u8>) -> SuiEventEnvelope {
+        SuiEventEnvelope {
+            timestamp: 1_676_000_000_000,
+            tx_digest: TransactionDigest::random(),
+            id: EventID {
+                tx_seq: 1,
+                event_seq: 0,
+            },
+            event: SuiEvent::MoveEvent {
+                package_id: ObjectID::from_hex_literal(PACKAGE).unwrap(),
+                transaction_module: Identifier::new("lemon").unwrap(),
+                sender: SuiAddress::random_for_testing_only(),
+                type_: format!("{PACKAGE}::lemon::{name}"),
+                fields: Some(SuiMoveStruct::WithFields(fields)),
+                bcs,
+            },
+        }
+    }
+
+    #[test]
+    fn decoders_agree_on_numeric_traits() {
+        // `cap` is declared as a string trait, so both decoders leave it out.
+        // Numbers beyond GraphQL's 32-bit `Int` are big numbers, starting at 2^31.
+        let envelope = numeric_lemon_created(&[
+            ("level", 5),
+            ("luck", i32::MAX as u64),
+            ("stamina", 1 << 31),
+            ("power", u64::MAX),
+            ("cap", 1),
+        ]);
+
+        let compared = registry(EventDecoder::Compare)
+            .parse(&envelope, ParseMode::Lenient)
+            .unwrap();
+        assert!(
+            !compared
+                .diagnostics
+                .iter()
+                .any(|d| matches!(d, Diagnostic::DecoderMismatch { .. })),
+            "{:?}",
+            compared.diagnostics
+        );
+        let decoded = registry(EventDecoder::Bcs)
+            .parse(&envelope, ParseMode::Lenient)
+            .unwrap();
+        assert_eq!(decoded.event, compared.event);
+        assert_eq!(decoded.diagnostics, compared.diagnostics);
+
+        let Event::Nft(nft) = decoded.event else {
+            panic!("Expected an NFT, got {:?}", decoded.event);
+        };
+        assert_eq!(
+            nft.traits,
+            vec![
+                Trait {
+                    name: "level".to_string(),
+                    flavour: TraitValue::Number(5),
+                },
+                Trait {
+                    name: "luck".to_string(),
+                    flavour: TraitValue::Number(i32::MAX.into()),
+                },
+                Trait {
+                    name: "stamina".to_string(),
+                    flavour: TraitValue::BigNumber((1u64 << 31).to_string()),
+                },
+                Trait {
+                    name: "power".to_string(),
+                    flavour: TraitValue::BigNumber(u64::MAX.to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn decoders_agree_on_slotted_attachments() {
+        for slot in [Some(Slot::Weapon), None] {
+            let envelope = item_added(slot.map(|slot| slot.as_str()));
+
+            // Strict parsing fails on the mismatch diagnostic, if any.
+            let compared = registry(EventDecoder::Compare)
+                .parse(&envelope, ParseMode::Strict)
+                .unwrap();
+            let Event::ItemAdded(item) = compared.event else {
+                panic!("Expected an attachment, got {:?}", compared.event);
+            };
+            assert_eq!(item.slot, slot);
+        }
+    }
 }
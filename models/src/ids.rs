@@ -0,0 +1,195 @@
+use crate::errors::Error;
+use async_graphql::{InputValueError, InputValueResult, Scalar, ScalarType, Value};
+use serde::{Deserialize, Serialize};
+use sqlx_core::decode::Decode;
+use sqlx_core::encode::{Encode, IsNull};
+use sqlx_core::error::BoxDynError;
+use sqlx_core::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef, Postgres};
+use sqlx_core::types::Type;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "sui-sdk")]
+use sui_sdk::types::base_types::{ObjectID, SuiAddress, SUI_ADDRESS_LENGTH};
+
+/// Length of addresses and object ids in bytes the stored ids are padded to, see the
+/// `normalize_nft_addresses` migration.
+const STORED_ADDRESS_LENGTH: usize = 20;
+
+/// Length of addresses and object ids in bytes, the Sui SDK's when it's available.
+#[cfg(feature = "sui-sdk")]
+const ADDRESS_LENGTH: usize = SUI_ADDRESS_LENGTH;
+#[cfg(not(feature = "sui-sdk"))]
+const ADDRESS_LENGTH: usize = STORED_ADDRESS_LENGTH;
+#[cfg(feature = "sui-sdk")]
+const _: () = assert!(
+    ADDRESS_LENGTH == STORED_ADDRESS_LENGTH,
+    "The Sui SDK's addresses don't have the length of the stored ones"
+);
+
+/// Number of hex digits in the canonical form of an address or object id.
+pub(crate) const HEX_LENGTH: usize = ADDRESS_LENGTH * 2;
+
+/// Normalize an address to `0x` followed by lowercase hex digits padded with leading zeros,
+/// which is how the Sui node displays them.
+fn canonicalize(kind: &'static str, value: &str) -> Result<String, Error> {
+    let invalid = |reason| Error::InvalidSuiId {
+        kind,
+        value: value.to_string(),
+        reason,
+    };
+
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    if hex.is_empty() {
+        return Err(invalid("no hex digits"));
+    }
+    if hex.len() > HEX_LENGTH {
+        return Err(invalid("too many hex digits"));
+    }
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid("non-hex characters"));
+    }
+
+    Ok(format!("0x{:0>HEX_LENGTH$}", hex.to_ascii_lowercase()))
+}
+
+macro_rules! sui_id {
+    ($(#[$meta:meta])* $name:ident, $kind:literal) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                canonicalize($kind, s).map(Self)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = Error;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        #[cfg(feature = "sui-sdk")]
+        impl From<SuiAddress> for $name {
+            fn from(address: SuiAddress) -> Self {
+                address
+                    .to_string()
+                    .parse()
+                    .expect("The Sui SDK displays valid addresses")
+            }
+        }
+
+        #[cfg(feature = "sui-sdk")]
+        impl From<ObjectID> for $name {
+            fn from(id: ObjectID) -> Self {
+                id.to_string()
+                    .parse()
+                    .expect("The Sui SDK displays valid object ids")
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        #[Scalar]
+        impl ScalarType for $name {
+            fn parse(value: Value) -> InputValueResult<Self> {
+                match &value {
+                    Value::String(s) => Ok(s.parse()?),
+                    _ => Err(InputValueError::expected_type(value)),
+                }
+            }
+
+            fn to_value(&self) -> Value {
+                Value::String(self.0.clone())
+            }
+        }
+
+        impl Type<Postgres> for $name {
+            fn type_info() -> PgTypeInfo {
+                <String as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <String as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl PgHasArrayType for $name {
+            fn array_type_info() -> PgTypeInfo {
+                <String as PgHasArrayType>::array_type_info()
+            }
+
+            fn array_compatible(ty: &PgTypeInfo) -> bool {
+                <String as PgHasArrayType>::array_compatible(ty)
+            }
+        }
+
+        impl Encode<'_, Postgres> for $name {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+                <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Postgres> for $name {
+            fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(<&str as Decode<Postgres>>::decode(value)?.parse()?)
+            }
+        }
+    };
+}
+
+sui_id!(
+    /// Sui account address in its canonical form.
+    Address,
+    "address"
+);
+
+sui_id!(
+    /// Sui object id in its canonical form.
+    ObjectId,
+    "object id"
+);
+
+#[cfg(all(test, feature = "sui-sdk"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdk_ids_equal_parsed_ones() {
+        let package = ObjectID::from_hex_literal("0x2").unwrap();
+        assert_eq!(ObjectId::from(package), "0x2".parse().unwrap());
+
+        let object_id = ObjectID::random();
+        let shouted = object_id.to_string().to_uppercase();
+        assert_eq!(ObjectId::from(object_id), shouted.parse().unwrap());
+
+        let address = SuiAddress::random_for_testing_only();
+        let shouted = address.to_string().to_uppercase();
+        assert_eq!(Address::from(address), shouted.parse().unwrap());
+    }
+}
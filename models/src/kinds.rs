@@ -0,0 +1,205 @@
+use crate::errors::Error;
+use crate::{Trait, TraitValue, TraitValueKind};
+use async_graphql::Enum;
+use serde::{Deserialize, Serialize};
+use sqlx_core::decode::Decode;
+use sqlx_core::encode::{Encode, IsNull};
+use sqlx_core::error::BoxDynError;
+use sqlx_core::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef, Postgres};
+use sqlx_core::types::Type;
+use std::fmt;
+use std::str::FromStr;
+
+/// Kind of NFT minted by the contract, what's known about each kind is in [`NftKind::spec`].
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NftKind {
+    Lemon,
+    Item,
+}
+
+/// What the indexer and the backend know about a kind of NFT.
+#[derive(Debug, Clone, Copy)]
+pub struct KindSpec {
+    /// Stored name of the kind.
+    pub name: &'static str,
+    /// Move module declaring the kind's struct.
+    pub module: &'static str,
+    /// Event the module emits when an NFT of the kind is minted.
+    pub created_event: &'static str,
+    /// Traits with a known value type, values of another type are left out when parsing.
+    /// Traits which aren't listed are kept as they are.
+    pub traits: &'static [TraitSpec],
+    /// Kinds which can be attached to NFTs of this kind.
+    pub accepts: &'static [NftKind],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TraitSpec {
+    pub name: &'static str,
+    pub kind: TraitValueKind,
+}
+
+const fn string_trait(name: &'static str) -> TraitSpec {
+    TraitSpec {
+        name,
+        kind: TraitValueKind::String,
+    }
+}
+
+const ITEM_TRAITS: &[TraitSpec] = &[string_trait(Slot::TRAIT)];
+
+const LEMON_TRAITS: &[TraitSpec] = &[
+    string_trait("cap"),
+    string_trait("cloth"),
+    string_trait("exo"),
+    string_trait("eyes"),
+    string_trait("head"),
+    string_trait("teeth"),
+];
+
+impl NftKind {
+    pub const ALL: [NftKind; 2] = [NftKind::Lemon, NftKind::Item];
+
+    pub const fn spec(&self) -> KindSpec {
+        match self {
+            NftKind::Lemon => KindSpec {
+                name: "lemon",
+                module: "lemon",
+                created_event: "LemonCreated",
+                traits: LEMON_TRAITS,
+                accepts: &[NftKind::Item],
+            },
+            NftKind::Item => KindSpec {
+                name: "item",
+                module: "item",
+                created_event: "ItemCreated",
+                traits: ITEM_TRAITS,
+                accepts: &[],
+            },
+        }
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        self.spec().name
+    }
+
+    /// Kind minted by `event` events, e.g. `LemonCreated`.
+    pub fn by_created_event(event: &str) -> Option<NftKind> {
+        NftKind::ALL
+            .into_iter()
+            .find(|kind| kind.spec().created_event == event)
+    }
+
+    /// Whether NFTs of the `other` kind can be attached to NFTs of this kind.
+    pub fn accepts(&self, other: NftKind) -> bool {
+        self.spec().accepts.contains(&other)
+    }
+
+    /// Expected type of the trait's values, if the kind declares the trait.
+    pub fn trait_kind(&self, name: &str) -> Option<TraitValueKind> {
+        self.spec()
+            .traits
+            .iter()
+            .find(|spec| spec.name == name)
+            .map(|spec| spec.kind)
+    }
+}
+
+impl FromStr for NftKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NftKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| Error::UnknownNftKind(s.to_string()))
+    }
+}
+
+impl fmt::Display for NftKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Equipment slot of a lemon, it holds at most one item.
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Slot {
+    Head,
+    Weapon,
+    Back,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 3] = [Slot::Head, Slot::Weapon, Slot::Back];
+    /// Name of the item trait holding the item's slot.
+    pub const TRAIT: &'static str = "slot";
+
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Slot::Head => "head",
+            Slot::Weapon => "weapon",
+            Slot::Back => "back",
+        }
+    }
+
+    /// Slot given by the `slot` trait, if it's a known one.
+    pub fn from_traits(traits: &[Trait]) -> Option<Slot> {
+        traits
+            .iter()
+            .find(|t| t.name == Slot::TRAIT)
+            .and_then(|t| match &t.flavour {
+                TraitValue::String(slot) => slot.parse().ok(),
+                _ => None,
+            })
+    }
+}
+
+impl FromStr for Slot {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Slot::ALL
+            .into_iter()
+            .find(|slot| slot.as_str() == s)
+            .ok_or_else(|| Error::UnknownSlot(s.to_string()))
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Store the enum as its `as_str` name in text columns.
+macro_rules! text_column {
+    ($name:ident) => {
+        impl Type<Postgres> for $name {
+            fn type_info() -> PgTypeInfo {
+                <String as Type<Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &PgTypeInfo) -> bool {
+                <String as Type<Postgres>>::compatible(ty)
+            }
+        }
+
+        impl Encode<'_, Postgres> for $name {
+            fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+                <&str as Encode<Postgres>>::encode(self.as_str(), buf)
+            }
+        }
+
+        impl<'r> Decode<'r, Postgres> for $name {
+            fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok(<&str as Decode<Postgres>>::decode(value)?.parse()?)
+            }
+        }
+    };
+}
+
+text_column!(NftKind);
+text_column!(Slot);
@@ -1,48 +1,670 @@
+// Lets the code generated by `models-derive` refer to `::models` from within this crate.
+extern crate self as models;
+
 pub mod errors;
+#[cfg(feature = "sui-sdk")]
 pub mod events;
+mod ids;
+mod kinds;
+#[cfg(feature = "sui-sdk")]
+pub mod move_events;
+#[cfg(feature = "sui-sdk")]
+pub mod move_struct;
+pub mod move_type;
+#[cfg(feature = "sui-sdk")]
+pub mod transactions;
 
-use async_graphql::{InputObject, SimpleObject};
+use crate::errors::Error;
+use async_graphql::registry::Registry;
+use async_graphql::{
+    ComplexObject, Enum, InputObject, InputType, InputValueError, InputValueResult, Interface,
+    Object, OneofObject, SimpleObject, Value, ID,
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
 use chrono::{DateTime, Utc};
+pub use ids::{Address, ObjectId};
+pub use kinds::{KindSpec, NftKind, Slot, TraitSpec};
 use serde::{Deserialize, Serialize};
 use sqlx_core::types::Json;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "sui-sdk")]
 pub use sui_sdk;
 
-#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone)]
+/// Sui network the data was indexed from.
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Network {
+    Localnet,
+    #[default]
+    Devnet,
+    Testnet,
+    Mainnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [
+        Network::Localnet,
+        Network::Devnet,
+        Network::Testnet,
+        Network::Mainnet,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Localnet => "localnet",
+            Network::Devnet => "devnet",
+            Network::Testnet => "testnet",
+            Network::Mainnet => "mainnet",
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|network| network.as_str() == s)
+            .ok_or_else(|| Error::UnknownNetwork(s.to_string()))
+    }
+}
+
+#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[graphql(input_name = "TraitInput")]
 pub struct Trait {
     pub name: String,
-    pub flavour: String,
+    pub flavour: TraitValue,
 }
 
-#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone)]
-#[graphql(input_name = "NftInput")]
+/// Value of a trait as the contract declares it, so numbers stay comparable as numbers.
+#[derive(OneofObject, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[graphql(name = "TraitValueInput")]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum TraitValue {
+    String(String),
+    /// Number within the range of GraphQL's 32-bit `Int`.
+    Number(i64),
+    /// Number outside the `Int` range, kept as its decimal digits so it isn't lost.
+    BigNumber(String),
+    Bool(bool),
+    List(Vec<TraitValue>),
+    /// Struct valued trait, its fields become traits themselves.
+    Nested(Vec<Trait>),
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraitValueKind {
+    String,
+    Number,
+    Bool,
+    List,
+    Nested,
+}
+
+impl TraitValueKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraitValueKind::String => "string",
+            TraitValueKind::Number => "number",
+            TraitValueKind::Bool => "bool",
+            TraitValueKind::List => "list",
+            TraitValueKind::Nested => "nested",
+        }
+    }
+}
+
+impl TraitValue {
+    pub fn value_kind(&self) -> TraitValueKind {
+        match self {
+            TraitValue::String(_) => TraitValueKind::String,
+            TraitValue::Number(_) | TraitValue::BigNumber(_) => TraitValueKind::Number,
+            TraitValue::Bool(_) => TraitValueKind::Bool,
+            TraitValue::List(_) => TraitValueKind::List,
+            TraitValue::Nested(_) => TraitValueKind::Nested,
+        }
+    }
+}
+
+/// Exactly one of the value fields is set, the one matching `kind`. Numbers outside the
+/// `Int` range are set in `bigNumber` rather than `number`.
+#[Object(name = "TraitValue")]
+impl TraitValue {
+    async fn kind(&self) -> TraitValueKind {
+        self.value_kind()
+    }
+
+    async fn string(&self) -> Option<&str> {
+        match self {
+            TraitValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    async fn number(&self) -> Option<i32> {
+        match self {
+            TraitValue::Number(value) => i32::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Also set for numbers stored before they were limited to the `Int` range.
+    async fn big_number(&self) -> Option<String> {
+        match self {
+            TraitValue::Number(value) if i32::try_from(*value).is_err() => Some(value.to_string()),
+            TraitValue::BigNumber(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    async fn bool(&self) -> Option<bool> {
+        match self {
+            TraitValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    async fn list(&self) -> Option<&[TraitValue]> {
+        match self {
+            TraitValue::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    async fn nested(&self) -> Option<&[Trait]> {
+        match self {
+            TraitValue::Nested(traits) => Some(traits),
+            _ => None,
+        }
+    }
+}
+
+/// Direct owner of an object, mirrors Sui's `Owner`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Ownership {
+    AddressOwner {
+        address: Address,
+    },
+    /// Wrapped in or attached as a dynamic field to another object.
+    ObjectOwner {
+        object: ObjectId,
+    },
+    Shared {
+        initial_shared_version: i64,
+    },
+    Immutable,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnershipKind {
+    AddressOwner,
+    ObjectOwner,
+    Shared,
+    Immutable,
+}
+
+/// Only the fields matching `kind` are set.
+#[Object(name = "Ownership")]
+impl Ownership {
+    async fn kind(&self) -> OwnershipKind {
+        match self {
+            Ownership::AddressOwner { .. } => OwnershipKind::AddressOwner,
+            Ownership::ObjectOwner { .. } => OwnershipKind::ObjectOwner,
+            Ownership::Shared { .. } => OwnershipKind::Shared,
+            Ownership::Immutable => OwnershipKind::Immutable,
+        }
+    }
+
+    async fn address(&self) -> Option<&Address> {
+        match self {
+            Ownership::AddressOwner { address } => Some(address),
+            _ => None,
+        }
+    }
+
+    async fn object(&self) -> Option<&ObjectId> {
+        match self {
+            Ownership::ObjectOwner { object } => Some(object),
+            _ => None,
+        }
+    }
+
+    async fn initial_shared_version(&self) -> Option<i64> {
+        match self {
+            Ownership::Shared {
+                initial_shared_version,
+            } => Some(*initial_shared_version),
+            _ => None,
+        }
+    }
+}
+
+/// Input form of [`Ownership`], `immutable` takes any value.
+#[derive(OneofObject, Debug, Clone)]
+pub enum OwnershipInput {
+    AddressOwner(Address),
+    ObjectOwner(ObjectId),
+    /// Initial shared version of the object.
+    Shared(i64),
+    Immutable(bool),
+}
+
+impl From<OwnershipInput> for Ownership {
+    fn from(input: OwnershipInput) -> Self {
+        match input {
+            OwnershipInput::AddressOwner(address) => Ownership::AddressOwner { address },
+            OwnershipInput::ObjectOwner(object) => Ownership::ObjectOwner { object },
+            OwnershipInput::Shared(initial_shared_version) => Ownership::Shared {
+                initial_shared_version,
+            },
+            OwnershipInput::Immutable(_) => Ownership::Immutable,
+        }
+    }
+}
+
+impl From<Ownership> for OwnershipInput {
+    fn from(ownership: Ownership) -> Self {
+        match ownership {
+            Ownership::AddressOwner { address } => OwnershipInput::AddressOwner(address),
+            Ownership::ObjectOwner { object } => OwnershipInput::ObjectOwner(object),
+            Ownership::Shared {
+                initial_shared_version,
+            } => OwnershipInput::Shared(initial_shared_version),
+            Ownership::Immutable => OwnershipInput::Immutable(true),
+        }
+    }
+}
+
+// `Ownership` is an output object, inputs go through the one-of `OwnershipInput`.
+impl InputType for Ownership {
+    type RawValueType = Self;
+
+    fn type_name() -> Cow<'static, str> {
+        OwnershipInput::type_name()
+    }
+
+    fn create_type_info(registry: &mut Registry) -> String {
+        OwnershipInput::create_type_info(registry)
+    }
+
+    fn parse(value: Option<Value>) -> InputValueResult<Self> {
+        OwnershipInput::parse(value)
+            .map(Into::into)
+            .map_err(InputValueError::propagate)
+    }
+
+    fn to_value(&self) -> Value {
+        OwnershipInput::from(self.clone()).to_value()
+    }
+
+    fn as_raw_value(&self) -> Option<&Self::RawValueType> {
+        Some(self)
+    }
+}
+
+#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[graphql(input_name = "NftInput", complex)]
 pub struct Nft {
-    pub id: String,
-    pub r#type: String,
-    pub owner: String,
+    pub id: ObjectId,
+    /// Network the NFT was indexed from, always set in responses.
+    #[graphql(skip_input)]
+    #[serde(default)]
+    pub network: Option<Network>,
+    pub r#type: NftKind,
+    /// Wallet which ultimately owns the NFT, following object owners up to an address.
+    /// For shared and immutable NFTs it's the creator.
+    pub owner: Address,
+    /// Direct owner, always set in responses. Inputs without it are owned by `owner`.
+    pub ownership: Option<Ownership>,
     pub url: String,
     pub traits: Vec<Trait>,
     pub items: Vec<Nft>,
     pub created_at: DateTime<Utc>,
-    pub attached_to: Option<String>,
+    pub attached_to: Option<ObjectId>,
+    /// Slot the NFT takes in the loadout of the one it's attached to.
+    pub slot: Option<Slot>,
 }
 
-#[derive(SimpleObject, Serialize, Deserialize, Debug, Clone)]
+#[ComplexObject]
+impl Nft {
+    /// Relay's global id of the NFT, see [`Node`].
+    async fn global_id(&self) -> ID {
+        let global_id = GlobalId::Nft {
+            network: self.network.unwrap_or_default(),
+            id: self.id.clone(),
+        };
+        ID(global_id.to_string())
+    }
+
+    /// Attached items keyed by their slot, items without one are only listed in `items`.
+    async fn loadout(&self) -> Loadout {
+        let mut loadout = Loadout::default();
+        for item in &self.items {
+            if let Some(slot) = item.slot {
+                *loadout.slot_mut(slot) = Some(item.clone());
+            }
+        }
+        loadout
+    }
+}
+
+impl Nft {
+    /// Sets the network the NFT and its items were read from.
+    pub fn on_network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self.items = self
+            .items
+            .into_iter()
+            .map(|item| item.on_network(network))
+            .collect();
+        self
+    }
+}
+
+#[derive(SimpleObject, Debug, Clone, Default)]
+pub struct Loadout {
+    pub head: Option<Nft>,
+    pub weapon: Option<Nft>,
+    pub back: Option<Nft>,
+}
+
+impl Loadout {
+    pub fn slot_mut(&mut self, slot: Slot) -> &mut Option<Nft> {
+        match slot {
+            Slot::Head => &mut self.head,
+            Slot::Weapon => &mut self.weapon,
+            Slot::Back => &mut self.back,
+        }
+    }
+}
+
+/// Relay's object identification, objects are refetched by their global id.
+///
+/// The same object id can be indexed from several networks, so the global id also holds the
+/// network, see [`GlobalId`]. It's exposed as `globalId`, `id` stays the object id.
+#[derive(Interface)]
+#[graphql(field(name = "global_id", ty = "ID"))]
+pub enum Node {
+    Nft(Nft),
+}
+
+/// Global id of a [`Node`], the base64 of `<type>:<network>:<object id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlobalId {
+    Nft { network: Network, id: ObjectId },
+}
+
+impl fmt::Display for GlobalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let GlobalId::Nft { network, id } = self;
+        let raw = format!("Nft:{}:{id}", network.as_str());
+        f.write_str(&STANDARD.encode(raw))
+    }
+}
+
+impl FromStr for GlobalId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidGlobalId(s.to_string());
+        let raw = STANDARD.decode(s).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        match raw.splitn(3, ':').collect::<Vec<_>>()[..] {
+            ["Nft", network, id] => Ok(GlobalId::Nft {
+                network: network.parse()?,
+                id: id.parse()?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Partial update of an NFT, fields which are `None` stay untouched.
+#[derive(InputObject, Serialize, Deserialize, Debug, Clone, Default)]
+#[graphql(name = "NftPatchInput")]
+pub struct NftPatch {
+    pub r#type: Option<NftKind>,
+    pub owner: Option<Address>,
+    pub url: Option<String>,
+    pub traits: Option<Vec<Trait>>,
+}
+
+/// Conditions on the NFTs of a query, an NFT has to meet all of them.
+#[derive(InputObject, Debug, Clone, Default)]
+pub struct NftFilter {
+    /// NFTs with one of the ids.
+    pub ids: Option<Vec<ObjectId>>,
+    /// NFTs having all of the traits.
+    #[graphql(default)]
+    pub traits: Vec<TraitFilter>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// NFTs attached to the given one.
+    pub attached_to: Option<ObjectId>,
+    /// Only NFTs which aren't attached to another one.
+    #[graphql(default)]
+    pub unattached: bool,
+}
+
+/// Order of the NFTs of a query, ties are broken by id.
+#[derive(InputObject, Debug, Clone)]
+pub struct NftOrder {
+    pub field: NftOrderField,
+    /// Trait to order by, required by the `TRAIT` field.
+    pub r#trait: Option<String>,
+    #[graphql(default)]
+    pub direction: OrderDirection,
+}
+
+impl Default for NftOrder {
+    fn default() -> Self {
+        Self {
+            field: NftOrderField::CreatedAt,
+            r#trait: None,
+            direction: OrderDirection::Asc,
+        }
+    }
+}
+
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftOrderField {
+    CreatedAt,
+    Id,
+    Owner,
+    /// Number of attached items.
+    ItemCount,
+    /// Value of the trait. In ascending order numbers come first and compare as numbers, then
+    /// strings and bools, then NFTs without the trait. Descending order is the exact reverse.
+    Trait,
+}
+
+impl NftOrderField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NftOrderField::CreatedAt => "created_at",
+            NftOrderField::Id => "id",
+            NftOrderField::Owner => "owner",
+            NftOrderField::ItemCount => "item_count",
+            NftOrderField::Trait => "trait",
+        }
+    }
+}
+
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrderDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Trait with the name, whose flavour is one of `flavours` when they're given.
+#[derive(InputObject, Serialize, Debug, Clone)]
+pub struct TraitFilter {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flavours: Option<Vec<TraitValue>>,
+}
+
+#[derive(SimpleObject, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Item {
-    pub lemon_id: String,
-    pub item_id: String,
+    pub lemon_id: ObjectId,
+    pub item_id: ObjectId,
+    /// Slot given by the event, otherwise it's derived from the item's traits.
+    pub slot: Option<Slot>,
+}
+
+/// Event of a type the indexer has no handler for, kept until it's re-processed.
+#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[graphql(input_name = "UnknownEventInput")]
+pub struct UnknownEvent {
+    /// Fully qualified Move type, e.g. `0x2::devnet_nft::MintNFTEvent`.
+    pub r#type: String,
+    pub sender: Address,
+    /// Fields as rendered by the Sui node.
+    pub fields: serde_json::Value,
+}
+
+/// Stored unknown event, identified by its transaction and position in it.
+#[derive(SimpleObject, Serialize, Deserialize, Debug, Clone)]
+pub struct UnknownEventRecord {
+    pub tx_digest: String,
+    pub event_seq: i64,
+    pub r#type: String,
+    pub sender: Address,
+    pub fields: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(SimpleObject, Serialize, Deserialize, Debug, Clone)]
+pub struct UnknownEventCount {
+    pub r#type: String,
+    pub count: i64,
+}
+
+/// Transaction which touched the contract's NFTs.
+#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone)]
+#[graphql(name = "Transaction", input_name = "TransactionInput")]
+pub struct TransactionRecord {
+    pub digest: String,
+    pub sender: Address,
+    pub timestamp: DateTime<Utc>,
+    /// Known only when the indexer walks checkpoints.
+    pub checkpoint: Option<i64>,
+    /// Computation and storage costs minus the storage rebate.
+    pub gas_used: i64,
+    pub status: TransactionStatus,
+    pub move_calls: Vec<MoveCall>,
+    /// Objects created, mutated, unwrapped, deleted or wrapped by the transaction.
+    pub affected_objects: Vec<ObjectId>,
+}
+
+#[derive(SimpleObject, InputObject, Serialize, Deserialize, Debug, Clone)]
+#[graphql(input_name = "MoveCallInput")]
+pub struct MoveCall {
+    pub package: String,
+    pub module: String,
+    pub function: String,
+}
+
+#[derive(Enum, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Success,
+    Failure,
+}
+
+impl TransactionStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TransactionStatus::Success => "success",
+            TransactionStatus::Failure => "failure",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionSql {
+    pub digest: String,
+    pub sender: Address,
+    pub timestamp: DateTime<Utc>,
+    pub checkpoint: Option<i64>,
+    pub gas_used: i64,
+    pub status: String,
+    pub move_calls: Json<Vec<MoveCall>>,
+    pub affected_objects: Vec<ObjectId>,
+}
+
+impl From<TransactionRecord> for TransactionSql {
+    fn from(
+        TransactionRecord {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls,
+            affected_objects,
+        }: TransactionRecord,
+    ) -> Self {
+        Self {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status: status.as_str().to_string(),
+            move_calls: Json(move_calls),
+            affected_objects,
+        }
+    }
+}
+
+impl From<TransactionSql> for TransactionRecord {
+    fn from(
+        TransactionSql {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls,
+            affected_objects,
+        }: TransactionSql,
+    ) -> Self {
+        let status = match status.as_str() {
+            "success" => TransactionStatus::Success,
+            _ => TransactionStatus::Failure,
+        };
+        Self {
+            digest,
+            sender,
+            timestamp,
+            checkpoint,
+            gas_used,
+            status,
+            move_calls: move_calls.0,
+            affected_objects,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NftSql {
-    pub id: String,
-    pub r#type: String,
-    pub owner: String,
+    pub id: ObjectId,
+    pub r#type: NftKind,
+    pub owner: Address,
+    pub ownership: Json<Ownership>,
     pub url: String,
     pub traits: Json<Vec<Trait>>,
     pub items: Json<Vec<NftSql>>,
     pub created_at: DateTime<Utc>,
-    pub attached_to: Option<String>,
+    pub attached_to: Option<ObjectId>,
+    pub slot: Option<Slot>,
 }
 
 impl From<Nft> for NftSql {
@@ -51,23 +673,31 @@ impl From<Nft> for NftSql {
             id,
             r#type,
             owner,
+            ownership,
             url,
             traits,
+            network: _,
             items,
             created_at,
             attached_to,
+            slot,
         }: Nft,
     ) -> Self {
         let items = items.into_iter().map(Into::into).collect();
+        let ownership = ownership.unwrap_or_else(|| Ownership::AddressOwner {
+            address: owner.clone(),
+        });
         Self {
             id,
             r#type,
             owner,
+            ownership: Json(ownership),
             url,
             traits: Json(traits),
             items: Json(items),
             created_at,
             attached_to,
+            slot,
         }
     }
 }
@@ -78,23 +708,28 @@ impl From<NftSql> for Nft {
             id,
             r#type,
             owner,
+            ownership,
             url,
             traits,
             items,
             created_at,
             attached_to,
+            slot,
         }: NftSql,
     ) -> Self {
         let items = items.0.into_iter().map(Into::into).collect();
         Self {
             id,
+            network: None,
             r#type,
             owner,
+            ownership: Some(ownership.0),
             url,
             traits: traits.0,
             items,
             created_at,
             attached_to,
+            slot,
         }
     }
 }
@@ -0,0 +1,119 @@
+//! Rust mirrors of the contract's Move event structs. Fields are declared in the Move order,
+//! so the structs decode the events' BCS payloads as well as their JSON fields.
+
+use crate::errors::Error;
+use crate::move_struct::{FromMoveStruct, MoveFields};
+use crate::{Address, Nft, NftKind, Ownership, Trait, TraitValue};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::base_types::SuiAddress;
+
+/// `Trait` struct of the `lemon` module. Flavours are strings, unless the event is generic
+/// over their type, e.g. `LemonCreated<u64>`.
+#[derive(FromMoveStruct, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MoveTrait<F = String> {
+    pub name: String,
+    pub flavour: F,
+}
+
+/// `LemonCreated` and `ItemCreated` events.
+#[derive(FromMoveStruct, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NftCreated<F = String> {
+    pub id: SuiAddress,
+    pub url: String,
+    pub traits: Vec<MoveTrait<F>>,
+}
+
+/// `ItemAdded` and `ItemRemoved` events. The slot is `none` when the item's traits decide it.
+#[derive(FromMoveStruct, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemAttachment {
+    pub lemon_id: SuiAddress,
+    pub item_id: SuiAddress,
+    pub slot: Option<String>,
+}
+
+/// Move type of trait flavours, converted like the node renders it in the JSON fields.
+pub trait MoveFlavour: DeserializeOwned {
+    fn into_trait_value(self) -> TraitValue;
+}
+
+impl MoveFlavour for String {
+    fn into_trait_value(self) -> TraitValue {
+        TraitValue::String(self)
+    }
+}
+
+impl MoveFlavour for bool {
+    fn into_trait_value(self) -> TraitValue {
+        TraitValue::Bool(self)
+    }
+}
+
+impl MoveFlavour for SuiAddress {
+    fn into_trait_value(self) -> TraitValue {
+        TraitValue::String(self.to_string())
+    }
+}
+
+macro_rules! number_flavour {
+    ($($ty:ty),*) => {
+        $(
+            impl MoveFlavour for $ty {
+                fn into_trait_value(self) -> TraitValue {
+                    match i32::try_from(self) {
+                        Ok(value) => TraitValue::Number(value.into()),
+                        Err(_) => TraitValue::BigNumber(self.to_string()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+number_flavour!(u8, u16, u32, u64);
+
+impl<F: MoveFlavour> NftCreated<F> {
+    pub fn into_nft(self, kind: NftKind, owner: SuiAddress, created_at: DateTime<Utc>) -> Nft {
+        let traits = self
+            .traits
+            .into_iter()
+            .map(|t| Trait {
+                name: t.name,
+                flavour: t.flavour.into_trait_value(),
+            })
+            .collect();
+
+        let owner: Address = owner.into();
+        Nft {
+            id: self.id.into(),
+            network: None,
+            r#type: kind,
+            ownership: Some(Ownership::AddressOwner {
+                address: owner.clone(),
+            }),
+            owner,
+            url: self.url,
+            traits,
+            items: Vec::new(),
+            created_at,
+            attached_to: None,
+            slot: None,
+        }
+    }
+}
+
+/// BCS payload of the `name` event with the given JSON fields, for events made up by
+/// generators and tests.
+pub fn encode_bcs(name: &str, fields: MoveFields) -> Result<Vec<u8>, Error> {
+    let bytes = match name {
+        "ItemAdded" | "ItemRemoved" => {
+            bcs::to_bytes(&ItemAttachment::from_move_struct(fields, "")?)
+        }
+        name if NftKind::by_created_event(name).is_some() => {
+            bcs::to_bytes(&NftCreated::<String>::from_move_struct(fields, "")?)
+        }
+        name => return Err(Error::UnknownEventType(name.to_string())),
+    }?;
+    Ok(bytes)
+}
@@ -0,0 +1,363 @@
+use crate::errors::Error;
+use crate::{Address, ObjectId};
+use std::collections::BTreeMap;
+use std::fmt;
+use sui_sdk::rpc_types::{SuiMoveStruct, SuiMoveValue};
+use sui_sdk::types::base_types::SuiAddress;
+
+pub use models_derive::FromMoveStruct;
+
+/// Fields of a Move struct, keyed by the field's name.
+pub type MoveFields = BTreeMap<String, SuiMoveValue>;
+
+/// Rust struct decoded from the fields of a Move struct, usually derived.
+pub trait FromMoveStruct: Sized {
+    /// Names of the Move fields the struct is decoded from.
+    const FIELDS: &'static [&'static str];
+
+    /// Decode the struct, `path` is the struct's path used in errors, empty for the root.
+    fn from_move_struct(fields: MoveFields, path: &str) -> Result<Self, Error>;
+}
+
+/// Rust value decoded from a Move value.
+pub trait FromMoveValue: Sized {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error>;
+
+    /// Value of a missing field, only optional values have one.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Decode the `name` field of the struct at `parent`.
+pub fn field<T: FromMoveValue>(
+    fields: &mut MoveFields,
+    parent: &str,
+    name: &str,
+) -> Result<T, Error> {
+    let path = field_path(parent, name);
+    match fields.remove(name) {
+        Some(value) => T::from_move_value(value, &path),
+        None => T::missing().ok_or(Error::WrongEventFieldName(path)),
+    }
+}
+
+/// Decode a struct nested in a Move value.
+pub fn struct_from_value<T: FromMoveStruct>(value: SuiMoveValue, path: &str) -> Result<T, Error> {
+    match value {
+        SuiMoveValue::Struct(
+            SuiMoveStruct::WithFields(fields) | SuiMoveStruct::WithTypes { fields, .. },
+        ) => T::from_move_struct(fields, path),
+        other => Err(mismatch(path, "struct", &other)),
+    }
+}
+
+/// Path of the `name` field of the struct at `parent`, e.g. `traits[2].flavour`.
+pub fn field_path(parent: &str, name: &str) -> String {
+    match parent {
+        "" => name.to_string(),
+        parent => format!("{parent}.{name}"),
+    }
+}
+
+pub(crate) fn value_kind(value: &SuiMoveValue) -> &'static str {
+    match value {
+        SuiMoveValue::Number(_) => "number",
+        SuiMoveValue::Bool(_) => "bool",
+        SuiMoveValue::Address(_) => "address",
+        SuiMoveValue::Vector(_) => "vector",
+        SuiMoveValue::String(_) => "string",
+        SuiMoveValue::UID { .. } => "uid",
+        SuiMoveValue::Struct(_) => "struct",
+        SuiMoveValue::Option(_) => "option",
+    }
+}
+
+fn mismatch(path: &str, expected: &'static str, found: &SuiMoveValue) -> Error {
+    Error::EventFieldType {
+        path: path.to_string(),
+        expected,
+        found: value_kind(found),
+    }
+}
+
+impl FromMoveValue for SuiMoveValue {
+    fn from_move_value(value: SuiMoveValue, _path: &str) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl FromMoveValue for String {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::String(value) => Ok(value),
+            other => Err(mismatch(path, "string", &other)),
+        }
+    }
+}
+
+impl FromMoveValue for u64 {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Number(value) => Ok(value),
+            other => Err(mismatch(path, "number", &other)),
+        }
+    }
+}
+
+/// Numbers wider than `u64` come as decimal strings, narrower ones may still be numbers.
+impl FromMoveValue for u128 {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Number(value) => Ok(value.into()),
+            SuiMoveValue::String(digits) => digits
+                .parse()
+                .map_err(|_| mismatch(path, "u128", &SuiMoveValue::String(digits))),
+            other => Err(mismatch(path, "u128", &other)),
+        }
+    }
+}
+
+/// Move `u256`, which has no Rust counterpart, kept as its decimal digits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct U256(String);
+
+impl U256 {
+    const MAX: &'static str =
+        "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+
+    /// Parse decimal digits, `None` unless they are a number within the `u256` range.
+    pub fn from_decimal(digits: &str) -> Option<Self> {
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let digits = match digits.trim_start_matches('0') {
+            "" => "0",
+            digits => digits,
+        };
+        // Numbers of the same length compare like their digits.
+        if (digits.len(), digits) > (Self::MAX.len(), Self::MAX) {
+            return None;
+        }
+        Some(Self(digits.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromMoveValue for U256 {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Number(value) => Ok(Self(value.to_string())),
+            SuiMoveValue::String(digits) => U256::from_decimal(&digits)
+                .ok_or_else(|| mismatch(path, "u256", &SuiMoveValue::String(digits))),
+            other => Err(mismatch(path, "u256", &other)),
+        }
+    }
+}
+
+impl FromMoveValue for bool {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Bool(value) => Ok(value),
+            other => Err(mismatch(path, "bool", &other)),
+        }
+    }
+}
+
+impl FromMoveValue for SuiAddress {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Address(value) => Ok(value),
+            other => Err(mismatch(path, "address", &other)),
+        }
+    }
+}
+
+impl FromMoveValue for Address {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        SuiAddress::from_move_value(value, path).map(Into::into)
+    }
+}
+
+/// Object ids are emitted either as plain addresses or as `UID` structs.
+impl FromMoveValue for ObjectId {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Address(value) => Ok(value.into()),
+            SuiMoveValue::UID { id } => Ok(id.into()),
+            other => Err(mismatch(path, "address", &other)),
+        }
+    }
+}
+
+impl<T: FromMoveValue> FromMoveValue for Vec<T> {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Vector(values) | SuiMoveValue::Struct(SuiMoveStruct::Runtime(values)) => {
+                values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, value)| T::from_move_value(value, &format!("{path}[{idx}]")))
+                    .collect()
+            }
+            other => Err(mismatch(path, "vector", &other)),
+        }
+    }
+}
+
+/// Both missing fields and empty Move options are decoded as `None`.
+impl<T: FromMoveValue> FromMoveValue for Option<T> {
+    fn from_move_value(value: SuiMoveValue, path: &str) -> Result<Self, Error> {
+        match value {
+            SuiMoveValue::Option(value) => match *value {
+                Some(value) => T::from_move_value(value, path).map(Some),
+                None => Ok(None),
+            },
+            value => T::from_move_value(value, path).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(FromMoveStruct, Debug, PartialEq)]
+    struct Sample {
+        #[move_struct(rename = "type")]
+        kind: String,
+        supply: u128,
+        total: U256,
+        parent: Option<ObjectId>,
+        tags: Vec<Tag>,
+    }
+
+    #[derive(FromMoveStruct, Debug, PartialEq)]
+    struct Tag {
+        name: String,
+        level: Option<u64>,
+    }
+
+    fn string(value: &str) -> SuiMoveValue {
+        SuiMoveValue::String(value.to_string())
+    }
+
+    fn tag(name: &str, level: Option<SuiMoveValue>) -> SuiMoveValue {
+        let mut fields = MoveFields::from([("name".to_string(), string(name))]);
+        if let Some(level) = level {
+            fields.insert("level".to_string(), level);
+        }
+        SuiMoveValue::Struct(SuiMoveStruct::WithFields(fields))
+    }
+
+    fn sample_fields() -> MoveFields {
+        MoveFields::from([
+            ("type".to_string(), string("lemon")),
+            ("supply".to_string(), string(&u128::MAX.to_string())),
+            ("total".to_string(), string(U256::MAX)),
+            ("parent".to_string(), SuiMoveValue::Option(Box::new(None))),
+            (
+                "tags".to_string(),
+                SuiMoveValue::Vector(vec![
+                    tag("cap", Some(SuiMoveValue::Number(3))),
+                    tag("eyes", Some(SuiMoveValue::Option(Box::new(None)))),
+                    tag("teeth", None),
+                ]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn derived_struct_is_decoded() {
+        let sample = Sample::from_move_struct(sample_fields(), "").unwrap();
+
+        assert_eq!(
+            Sample::FIELDS,
+            ["type", "supply", "total", "parent", "tags"]
+        );
+        assert_eq!(
+            sample,
+            Sample {
+                kind: "lemon".to_string(),
+                supply: u128::MAX,
+                total: U256::from_decimal(U256::MAX).unwrap(),
+                parent: None,
+                tags: vec![
+                    Tag {
+                        name: "cap".to_string(),
+                        level: Some(3),
+                    },
+                    Tag {
+                        name: "eyes".to_string(),
+                        level: None,
+                    },
+                    Tag {
+                        name: "teeth".to_string(),
+                        level: None,
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn renamed_field_is_required_under_its_move_name() {
+        let mut fields = sample_fields();
+        let kind = fields.remove("type").unwrap();
+        fields.insert("kind".to_string(), kind);
+
+        let err = Sample::from_move_struct(fields, "").unwrap_err();
+        assert!(
+            matches!(&err, Error::WrongEventFieldName(path) if path == "type"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn errors_carry_the_nested_path() {
+        let mut fields = sample_fields();
+        fields.insert(
+            "tags".to_string(),
+            SuiMoveValue::Vector(vec![tag("cap", Some(string("high")))]),
+        );
+
+        let err = Sample::from_move_struct(fields, "").unwrap_err();
+        assert!(
+            matches!(&err, Error::EventFieldType { path, .. } if path == "tags[0].level"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn wide_numbers_out_of_range_are_rejected() {
+        assert!(
+            u128::from_move_value(string("340282366920938463463374607431768211456"), "").is_err()
+        );
+        assert!(u128::from_move_value(string("-1"), "").is_err());
+        assert_eq!(
+            u128::from_move_value(SuiMoveValue::Number(u64::MAX), "").unwrap(),
+            u64::MAX.into()
+        );
+
+        let beyond_u256 =
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+        assert!(U256::from_decimal(beyond_u256).is_none());
+        assert!(U256::from_decimal("12a").is_none());
+        assert!(U256::from_decimal("").is_none());
+        assert_eq!(U256::from_decimal("007").unwrap().as_str(), "7");
+        assert_eq!(U256::from_decimal("000").unwrap().as_str(), "0");
+    }
+}
@@ -0,0 +1,303 @@
+use crate::errors::Error;
+use crate::ObjectId;
+use std::fmt;
+use std::str::FromStr;
+
+/// Move type as it appears in event types, e.g. `0x2::coin::Coin<0x2::sui::SUI>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeTag {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector(Box<TypeTag>),
+    Struct(StructTag),
+}
+
+/// Fully qualified Move struct type, `package::module::Name<T1, T2>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StructTag {
+    pub package: ObjectId,
+    pub module: String,
+    pub name: String,
+    pub type_params: Vec<TypeTag>,
+}
+
+impl fmt::Display for TypeTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeTag::Bool => write!(f, "bool"),
+            TypeTag::U8 => write!(f, "u8"),
+            TypeTag::U16 => write!(f, "u16"),
+            TypeTag::U32 => write!(f, "u32"),
+            TypeTag::U64 => write!(f, "u64"),
+            TypeTag::U128 => write!(f, "u128"),
+            TypeTag::U256 => write!(f, "u256"),
+            TypeTag::Address => write!(f, "address"),
+            TypeTag::Signer => write!(f, "signer"),
+            TypeTag::Vector(inner) => write!(f, "vector<{inner}>"),
+            TypeTag::Struct(tag) => write!(f, "{tag}"),
+        }
+    }
+}
+
+impl fmt::Display for StructTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}::{}", self.package, self.module, self.name)?;
+        if !self.type_params.is_empty() {
+            let params = self
+                .type_params
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "<{params}>")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TypeTag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_all(Parser::type_tag)
+    }
+}
+
+impl FromStr for StructTag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::new(s).parse_all(Parser::struct_tag)
+    }
+}
+
+/// Recursive descent parser over the type's tokens, `::`, `<`, `>` and `,` are punctuation
+/// and everything else is an identifier or an address.
+struct Parser<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, rest: input }
+    }
+
+    fn parse_all<T>(mut self, parse: fn(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        let value = parse(&mut self)?;
+        self.skip_whitespace();
+        if !self.rest.is_empty() {
+            return Err(self.error(format!("unexpected `{}` at the end", self.rest)));
+        }
+        Ok(value)
+    }
+
+    fn type_tag(&mut self) -> Result<TypeTag, Error> {
+        let ident = self.ident()?;
+        let tag = match ident {
+            "bool" => TypeTag::Bool,
+            "u8" => TypeTag::U8,
+            "u16" => TypeTag::U16,
+            "u32" => TypeTag::U32,
+            "u64" => TypeTag::U64,
+            "u128" => TypeTag::U128,
+            "u256" => TypeTag::U256,
+            "address" => TypeTag::Address,
+            "signer" => TypeTag::Signer,
+            "vector" => {
+                self.expect("<")?;
+                let inner = self.type_tag()?;
+                self.expect(">")?;
+                TypeTag::Vector(Box::new(inner))
+            }
+            package => TypeTag::Struct(self.struct_tail(package)?),
+        };
+        Ok(tag)
+    }
+
+    fn struct_tag(&mut self) -> Result<StructTag, Error> {
+        let package = self.ident()?;
+        self.struct_tail(package)
+    }
+
+    fn struct_tail(&mut self, package: &str) -> Result<StructTag, Error> {
+        let package = package
+            .parse::<ObjectId>()
+            .map_err(|err| self.error(err.to_string()))?;
+        self.expect("::")?;
+        let module = self.ident()?.to_string();
+        self.expect("::")?;
+        let name = self.ident()?.to_string();
+
+        let mut type_params = Vec::new();
+        if self.eat("<") {
+            loop {
+                type_params.push(self.type_tag()?);
+                if self.eat(">") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        Ok(StructTag {
+            package,
+            module,
+            name,
+            type_params,
+        })
+    }
+
+    fn ident(&mut self) -> Result<&'a str, Error> {
+        self.skip_whitespace();
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(match self.rest.chars().next() {
+                Some(c) => self.error(format!("expected an identifier, found `{c}`")),
+                None => self.error("expected an identifier, found the end".to_string()),
+            });
+        }
+        let (ident, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Ok(ident)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), Error> {
+        if self.eat(token) {
+            return Ok(());
+        }
+        Err(match self.rest.is_empty() {
+            true => self.error(format!("expected `{token}`, found the end")),
+            false => self.error(format!("expected `{token}` before `{}`", self.rest)),
+        })
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn error(&self, reason: String) -> Error {
+        Error::InvalidMoveType {
+            type_: self.input.to_string(),
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ids::HEX_LENGTH;
+
+    fn sui() -> StructTag {
+        StructTag {
+            package: "0x2".parse().unwrap(),
+            module: "sui".to_string(),
+            name: "SUI".to_string(),
+            type_params: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn short_address_is_canonicalized() {
+        let tag: StructTag = "0x2::sui::SUI".parse().unwrap();
+
+        assert_eq!(tag, sui());
+        assert_eq!(
+            tag.to_string(),
+            format!("0x{:0>HEX_LENGTH$}::sui::SUI", 2),
+            "Display must print the canonical address"
+        );
+    }
+
+    #[test]
+    fn nested_generics_are_parsed() {
+        let tag: StructTag = "0x2::coin::Coin<vector<0x2::balance::Balance<0x2::sui::SUI>>, u64>"
+            .parse()
+            .unwrap();
+
+        let balance = StructTag {
+            package: "0x2".parse().unwrap(),
+            module: "balance".to_string(),
+            name: "Balance".to_string(),
+            type_params: vec![TypeTag::Struct(sui())],
+        };
+        assert_eq!(tag.module, "coin");
+        assert_eq!(tag.name, "Coin");
+        assert_eq!(
+            tag.type_params,
+            vec![
+                TypeTag::Vector(Box::new(TypeTag::Struct(balance))),
+                TypeTag::U64
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_between_tokens_is_ignored() {
+        let spaced: StructTag = " 0x2 :: coin :: Coin < 0x2 :: sui :: SUI > "
+            .parse()
+            .unwrap();
+        let compact: StructTag = "0x2::coin::Coin<0x2::sui::SUI>".parse().unwrap();
+
+        assert_eq!(spaced, compact);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let input = format!(
+            "0x{:0>HEX_LENGTH$}::coin::Coin<vector<u8>, 0x{:0>HEX_LENGTH$}::sui::SUI>",
+            2, 2
+        );
+        let tag: StructTag = input.parse().unwrap();
+
+        assert_eq!(tag.to_string(), input);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        for input in [
+            "",
+            "0x2",
+            "0x2::coin",
+            "0x2::coin::",
+            "0x2::coin::Coin<",
+            "0x2::coin::Coin<u64",
+            "0x2::coin::Coin<u64,>",
+            "0x2::coin::Coin<>",
+            "0x2::coin::Coin u64",
+            "0x2::coin::Coin<u64>>",
+            "0xzz::coin::Coin",
+            "u64",
+        ] {
+            assert!(
+                matches!(
+                    input.parse::<StructTag>(),
+                    Err(Error::InvalidMoveType { .. })
+                ),
+                "`{input}` must be rejected"
+            );
+        }
+    }
+}
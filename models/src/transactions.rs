@@ -0,0 +1,64 @@
+use crate::{MoveCall, ObjectId, TransactionRecord, TransactionStatus};
+use chrono::{TimeZone, Utc};
+use sui_sdk::rpc_types::{SuiExecutionStatus, SuiTransactionKind, SuiTransactionResponse};
+
+impl TransactionRecord {
+    /// Build the record from the node's response, the timestamp and checkpoint come from the
+    /// indexer, since the response doesn't reliably carry them.
+    pub fn from_response(
+        tx: &SuiTransactionResponse,
+        timestamp_ms: u64,
+        checkpoint: Option<u64>,
+    ) -> Self {
+        let data = &tx.certificate.data;
+        let effects = &tx.effects;
+
+        let move_calls = data
+            .transactions
+            .iter()
+            .filter_map(|kind| match kind {
+                SuiTransactionKind::Call(call) => Some(MoveCall {
+                    package: call.package.to_string(),
+                    module: call.module.clone(),
+                    function: call.function.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        // The gas coin is mutated by every transaction, so it isn't worth recording.
+        let gas_object = effects.gas_object.reference.object_id;
+        let affected_objects = effects
+            .created
+            .iter()
+            .chain(&effects.mutated)
+            .chain(&effects.unwrapped)
+            .map(|object| object.reference.object_id)
+            .chain(effects.deleted.iter().map(|object| object.object_id))
+            .chain(effects.wrapped.iter().map(|object| object.object_id))
+            .filter(|object_id| *object_id != gas_object)
+            .map(ObjectId::from)
+            .collect();
+
+        let gas = &effects.gas_used;
+        let gas_used = (gas.computation_cost + gas.storage_cost).saturating_sub(gas.storage_rebate);
+        let status = match effects.status {
+            SuiExecutionStatus::Success => TransactionStatus::Success,
+            SuiExecutionStatus::Failure { .. } => TransactionStatus::Failure,
+        };
+
+        Self {
+            digest: tx.certificate.transaction_digest.to_string(),
+            sender: data.sender.into(),
+            timestamp: Utc
+                .timestamp_millis_opt(timestamp_ms as i64)
+                .single()
+                .unwrap_or_else(Utc::now),
+            checkpoint: checkpoint.map(|checkpoint| checkpoint as i64),
+            gas_used: gas_used as i64,
+            status,
+            move_calls,
+            affected_objects,
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! In-process fake Sui node for end-to-end tests.
+//!
+//! It speaks just enough of the JSON-RPC over HTTP and websocket protocol used by
+//! `SuiClientBuilder` to build a client, subscribe to events, page through historical events,
+//! fetch transactions and walk checkpoints. Tests script it by emitting events, sealing them
+//! into checkpoints, queueing errors for the next calls of a method and dropping open
+//! websocket connections.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use futures::{SinkExt, StreamExt};
+use models::sui_sdk::rpc_types::{SuiEvent, SuiEventEnvelope};
+use models::sui_sdk::types::base_types::{ObjectDigest, ObjectID, TransactionDigest};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+/// Ed25519 flag, zeroed signature and the curve's base point as the public key.
+const TX_SIGNATURE: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABYZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZmZg==";
+/// Compressed BLS12-381 point at infinity.
+const AUTHORITY_SIGNATURE: &str =
+    "wAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+/// Empty roaring bitmap.
+const SIGNERS_MAP: &str = "OjAAAAAAAAA=";
+const METHODS: [&str; 7] = [
+    "sui_getCheckpointContents",
+    "sui_getCheckpointContentsBySequenceNumber",
+    "sui_getEvents",
+    "sui_getLatestCheckpointSequenceNumber",
+    "sui_getTransaction",
+    "sui_subscribeEvent",
+    "sui_unsubscribeEvent",
+];
+
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+struct MockCheckpoint {
+    digest: String,
+    transactions: Vec<String>,
+}
+
+struct MockState {
+    api_version: String,
+    events: broadcast::Sender<Value>,
+    history: Mutex<Vec<Value>>,
+    transactions: Mutex<HashMap<String, Value>>,
+    checkpoints: Mutex<Vec<MockCheckpoint>>,
+    failures: Mutex<HashMap<String, VecDeque<RpcError>>>,
+    disconnects: watch::Sender<u64>,
+    subscribers: watch::Sender<usize>,
+    next_subscription: Mutex<u64>,
+}
+
+pub struct MockSuiNode {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+}
+
+impl MockSuiNode {
+    /// Start the node on a random local port.
+    pub async fn start(api_version: &str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind mock address");
+        let addr = listener.local_addr().unwrap();
+        let (events, _) = broadcast::channel(1024);
+        let (disconnects, _) = watch::channel(0);
+        let (subscribers, _) = watch::channel(0);
+        let state = Arc::new(MockState {
+            api_version: api_version.to_string(),
+            events,
+            history: Mutex::new(Vec::new()),
+            transactions: Mutex::new(HashMap::new()),
+            checkpoints: Mutex::new(vec![MockCheckpoint::new(Vec::new())]),
+            failures: Mutex::new(HashMap::new()),
+            disconnects,
+            subscribers,
+            next_subscription: Mutex::new(1),
+        });
+
+        let router = Router::new()
+            .route("/", get(ws_handler).post(http_handler))
+            .with_state(state.clone());
+        let server = axum::Server::from_tcp(listener)
+            .expect("Failed to start mock server")
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+
+        Self { addr, state }
+    }
+
+    pub fn http_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Push the event to all subscribers and keep it for `sui_getEvents`.
+    pub fn emit(&self, envelope: &SuiEventEnvelope) {
+        let envelope = serde_json::to_value(envelope).expect("Failed to serialize event");
+        self.state.history.lock().unwrap().push(envelope.clone());
+        let _ = self.state.events.send(envelope);
+    }
+
+    /// Store the response returned by `sui_getTransaction` for the digest.
+    pub fn add_transaction(&self, digest: &TransactionDigest, response: Value) {
+        self.state
+            .transactions
+            .lock()
+            .unwrap()
+            .insert(digest.to_string(), response);
+    }
+
+    /// Seal the transactions into the next checkpoint and return its sequence number.
+    ///
+    /// The node starts with an empty genesis checkpoint 0.
+    pub fn add_checkpoint(&self, transactions: &[TransactionDigest]) -> u64 {
+        let mut checkpoints = self.state.checkpoints.lock().unwrap();
+        checkpoints.push(MockCheckpoint::new(
+            transactions.iter().map(ToString::to_string).collect(),
+        ));
+        checkpoints.len() as u64 - 1
+    }
+
+    /// Digest of the checkpoint's contents, as taken by `sui_getCheckpointContents`.
+    pub fn checkpoint_digest(&self, sequence_number: u64) -> Option<String> {
+        self.state
+            .checkpoints
+            .lock()
+            .unwrap()
+            .get(sequence_number as usize)
+            .map(|checkpoint| checkpoint.digest.clone())
+    }
+
+    /// Make the next call of the method fail with the error.
+    pub fn fail_next(&self, method: &str, code: i64, message: &str) {
+        self.state
+            .failures
+            .lock()
+            .unwrap()
+            .entry(method.to_string())
+            .or_default()
+            .push_back(RpcError {
+                code,
+                message: message.to_string(),
+            });
+    }
+
+    /// Close all open websocket connections, which ends their subscriptions.
+    pub fn drop_connections(&self) {
+        self.state.disconnects.send_modify(|n| *n += 1);
+    }
+
+    pub fn subscribers(&self) -> usize {
+        *self.state.subscribers.borrow()
+    }
+
+    /// Wait until there are at least `n` active subscriptions.
+    pub async fn wait_for_subscribers(&self, n: usize, timeout: Duration) -> bool {
+        let mut subscribers = self.state.subscribers.subscribe();
+        tokio::time::timeout(timeout, async {
+            while *subscribers.borrow_and_update() < n {
+                if subscribers.changed().await.is_err() {
+                    return false;
+                }
+            }
+            true
+        })
+        .await
+        .unwrap_or(false)
+    }
+}
+
+/// Response of `sui_getTransaction` for a successful transaction which called `function` of
+/// the events' module and emitted them, which all must come from the same transaction.
+///
+/// Signatures are well-formed placeholders, clients don't verify them.
+pub fn transaction_response(function: &str, events: &[SuiEventEnvelope]) -> Value {
+    let first = events.first().expect("Transaction must emit events");
+    let SuiEvent::MoveEvent {
+        package_id,
+        transaction_module,
+        sender,
+        ..
+    } = &first.event
+    else {
+        panic!("Transaction must emit Move events");
+    };
+    let object_ref = |object_id: ObjectID| {
+        json!({
+            "objectId": object_id,
+            "version": 1,
+            "digest": ObjectDigest::new([0; 32]),
+        })
+    };
+    let gas_object = object_ref(ObjectID::random());
+
+    json!({
+        "certificate": {
+            "transactionDigest": first.tx_digest,
+            "data": {
+                "transactions": [{
+                    "Call": {
+                        "package": package_id,
+                        "module": transaction_module.to_string(),
+                        "function": function,
+                        "typeArguments": [],
+                        "arguments": [],
+                    },
+                }],
+                "sender": sender,
+                "gasPayment": gas_object,
+                "gasPrice": 1,
+                "gasBudget": 10_000,
+            },
+            "txSignatures": [TX_SIGNATURE],
+            "authSignInfo": {
+                "epoch": 0,
+                "signature": AUTHORITY_SIGNATURE,
+                "signers_map": SIGNERS_MAP,
+            },
+        },
+        "effects": {
+            "status": { "status": "success" },
+            "executedEpoch": 0,
+            "gasUsed": { "computationCost": 100, "storageCost": 50, "storageRebate": 20 },
+            "sharedObjects": [],
+            "transactionDigest": first.tx_digest,
+            "created": [],
+            "mutated": [],
+            "unwrapped": [],
+            "deleted": [],
+            "wrapped": [],
+            "gasObject": {
+                "owner": { "AddressOwner": sender },
+                "reference": gas_object,
+            },
+            "events": events.iter().map(|envelope| &envelope.event).collect::<Vec<_>>(),
+            "dependencies": [],
+        },
+        "timestampMs": first.timestamp,
+        "checkpoint": null,
+        "parsedData": null,
+    })
+}
+
+async fn http_handler(State(state): State<Arc<MockState>>, Json(req): Json<Value>) -> Json<Value> {
+    let id = req.get("id").cloned().unwrap_or(Value::Null);
+    let method = req
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let params = req.get("params").cloned().unwrap_or(Value::Null);
+
+    Json(response(id, state.call(method, &params)))
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<MockState>>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: Arc<MockState>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = state.events.subscribe();
+    let mut disconnects = state.disconnects.subscribe();
+    let mut subscriptions: Vec<u64> = Vec::new();
+
+    loop {
+        tokio::select! {
+            msg = receiver.next() => {
+                let Some(Ok(Message::Text(text))) = msg else { break };
+                let Ok(req) = serde_json::from_str::<Value>(&text) else { continue };
+                let id = req.get("id").cloned().unwrap_or(Value::Null);
+                let method = req.get("method").and_then(Value::as_str).unwrap_or_default();
+                let params = req.get("params").cloned().unwrap_or(Value::Null);
+
+                let ret = match method {
+                    "sui_subscribeEvent" => state.subscribe().map(|subscription| {
+                        subscriptions.push(subscription);
+                        json!(subscription)
+                    }),
+                    "sui_unsubscribeEvent" => {
+                        let subscription = params.get(0).and_then(Value::as_u64);
+                        let before = subscriptions.len();
+                        subscriptions.retain(|s| Some(*s) != subscription);
+                        state.unsubscribe(before - subscriptions.len());
+                        Ok(json!(before != subscriptions.len()))
+                    }
+                    method => state.call(method, &params),
+                };
+                let resp = response(id, ret).to_string();
+                if sender.send(Message::Text(resp)).await.is_err() {
+                    break;
+                }
+            }
+            Ok(envelope) = events.recv(), if !subscriptions.is_empty() => {
+                for subscription in &subscriptions {
+                    let notification = json!({
+                        "jsonrpc": "2.0",
+                        "method": "sui_subscribeEvent",
+                        "params": { "subscription": subscription, "result": envelope },
+                    });
+                    if sender.send(Message::Text(notification.to_string())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            _ = disconnects.changed() => {
+                let _ = sender.close().await;
+                break;
+            }
+        }
+    }
+
+    state.unsubscribe(subscriptions.len());
+}
+
+fn response(id: Value, ret: Result<Value, RpcError>) -> Value {
+    match ret {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(RpcError { code, message }) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+impl MockCheckpoint {
+    fn new(transactions: Vec<String>) -> Self {
+        Self {
+            // Any base58 encoded 32 bytes will do, the contents aren't actually hashed.
+            digest: TransactionDigest::random().to_string(),
+            transactions,
+        }
+    }
+
+    fn contents(&self) -> Value {
+        let transactions: Vec<Value> = self
+            .transactions
+            .iter()
+            .map(|digest| json!({ "transaction": digest, "effects": digest }))
+            .collect();
+
+        json!({ "transactions": transactions, "user_signatures": [] })
+    }
+}
+
+impl MockState {
+    fn take_failure(&self, method: &str) -> Option<RpcError> {
+        self.failures
+            .lock()
+            .unwrap()
+            .get_mut(method)
+            .and_then(VecDeque::pop_front)
+    }
+
+    fn subscribe(&self) -> Result<u64, RpcError> {
+        if let Some(err) = self.take_failure("sui_subscribeEvent") {
+            return Err(err);
+        }
+
+        let mut next_subscription = self.next_subscription.lock().unwrap();
+        let subscription = *next_subscription;
+        *next_subscription += 1;
+        self.subscribers.send_modify(|n| *n += 1);
+
+        Ok(subscription)
+    }
+
+    fn unsubscribe(&self, count: usize) {
+        self.subscribers
+            .send_modify(|n| *n = n.saturating_sub(count));
+    }
+
+    fn call(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        if let Some(err) = self.take_failure(method) {
+            return Err(err);
+        }
+
+        match method {
+            "rpc.discover" => Ok(json!({
+                "openrpc": "1.2.6",
+                "info": { "title": "Sui JSON-RPC", "version": self.api_version },
+                "methods": METHODS.iter().map(|name| json!({ "name": name })).collect::<Vec<_>>(),
+            })),
+            "sui_getEvents" => Ok(self.get_events(params)),
+            "sui_getTransaction" => {
+                let digest = params.get(0).and_then(Value::as_str).unwrap_or_default();
+                self.transactions
+                    .lock()
+                    .unwrap()
+                    .get(digest)
+                    .cloned()
+                    .ok_or_else(|| RpcError {
+                        code: -32602,
+                        message: format!("Transaction `{digest}` is not found"),
+                    })
+            }
+            "sui_getLatestCheckpointSequenceNumber" => {
+                Ok(json!(self.checkpoints.lock().unwrap().len() - 1))
+            }
+            "sui_getCheckpointContentsBySequenceNumber" => {
+                let sequence_number = params.get(0).and_then(Value::as_u64);
+                let checkpoints = self.checkpoints.lock().unwrap();
+                sequence_number
+                    .and_then(|seq| checkpoints.get(seq as usize))
+                    .map(MockCheckpoint::contents)
+                    .ok_or_else(|| RpcError {
+                        code: -32602,
+                        message: format!("Checkpoint {params} is not found"),
+                    })
+            }
+            "sui_getCheckpointContents" => {
+                let digest = params.get(0).and_then(Value::as_str).unwrap_or_default();
+                let checkpoints = self.checkpoints.lock().unwrap();
+                checkpoints
+                    .iter()
+                    .find(|checkpoint| checkpoint.digest == digest)
+                    .map(MockCheckpoint::contents)
+                    .ok_or_else(|| RpcError {
+                        code: -32602,
+                        message: format!("Checkpoint `{digest}` is not found"),
+                    })
+            }
+            other => Err(RpcError {
+                code: -32601,
+                message: format!("Method `{other}` is not found"),
+            }),
+        }
+    }
+
+    /// Serve a page of emitted events, supporting `All`, `Transaction`, `MoveModule` and
+    /// `TimeRange` queries.
+    fn get_events(&self, params: &Value) -> Value {
+        let query = params.get(0).cloned().unwrap_or(Value::Null);
+        let cursor = params.get(1).filter(|c| !c.is_null());
+        let limit = params
+            .get(2)
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_PAGE_SIZE, |limit| limit as usize);
+
+        let history = self.history.lock().unwrap();
+        let start = cursor
+            .and_then(|cursor| history.iter().position(|e| e.get("id") == Some(cursor)))
+            .map_or(0, |pos| pos + 1);
+        let mut matching = history[start..]
+            .iter()
+            .filter(|envelope| matches_query(&query, envelope));
+
+        let data: Vec<Value> = matching.by_ref().take(limit).cloned().collect();
+        let next_cursor = match matching.next() {
+            Some(_) => data.last().and_then(|e| e.get("id")).cloned(),
+            None => None,
+        };
+
+        json!({ "data": data, "nextCursor": next_cursor })
+    }
+}
+
+fn matches_query(query: &Value, envelope: &Value) -> bool {
+    if let Some(digest) = query.get("Transaction") {
+        return envelope.get("txDigest") == Some(digest);
+    }
+
+    if let Some(module) = query.get("MoveModule") {
+        let event = envelope
+            .get("event")
+            .and_then(|event| event.get("moveEvent"));
+        let field = |snake: &str, camel: &str| {
+            event.and_then(|event| event.get(snake).or_else(|| event.get(camel)))
+        };
+        return field("package_id", "packageId") == module.get("package")
+            && field("transaction_module", "transactionModule") == module.get("module");
+    }
+
+    if let Some(range) = query.get("TimeRange") {
+        let bound = |snake: &str, camel: &str| {
+            range
+                .get(snake)
+                .or_else(|| range.get(camel))
+                .and_then(Value::as_u64)
+        };
+        let timestamp = envelope.get("timestamp").and_then(Value::as_u64);
+        let (Some(start), Some(end), Some(timestamp)) = (
+            bound("start_time", "startTime"),
+            bound("end_time", "endTime"),
+            timestamp,
+        ) else {
+            return false;
+        };
+        return (start..end).contains(&timestamp);
+    }
+
+    true
+}